@@ -0,0 +1,104 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use diesel::prelude::*;
+
+use crate::export::{write_export, ClickRow, ExportFormat, ExportQuery, LinkRow, Owner};
+
+pub const EXPORT_USAGE: &str = "usage: analytics-service export <links|clicks> (--user-id <id> | --all) \
+[--format csv|jsonl|parquet] [--from <date>] [--to <date>] [--output <path>]
+
+Dates are RFC 3339 timestamps or YYYY-MM-DD (UTC midnight); --from is inclusive, --to exclusive.
+Without --output the export is written to stdout.";
+
+enum Table {
+    Links,
+    Clicks,
+}
+
+struct ExportArgs {
+    table: Table,
+    query: ExportQuery,
+    format: ExportFormat,
+    output: Option<String>,
+}
+
+fn parse_time(value: &str) -> Result<NaiveDateTime, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.naive_utc());
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|day| day.and_hms_opt(0, 0, 0).expect("midnight exists every day"))
+        .map_err(|_| format!("invalid date: {}", value))
+}
+
+fn parse_args(args: &[String]) -> Result<ExportArgs, String> {
+    let mut args = args.iter();
+    let table = match args.next().map(String::as_str) {
+        Some("links") => Table::Links,
+        Some("clicks") => Table::Clicks,
+        _ => return Err("expected `links` or `clicks`".to_string()),
+    };
+
+    let mut owner = None;
+    let mut format = ExportFormat::default();
+    let mut from = None;
+    let mut to = None;
+    let mut output = None;
+
+    while let Some(flag) = args.next() {
+        if flag == "--all" {
+            owner = Some(Owner::All);
+            continue;
+        }
+        let value = args.next().ok_or_else(|| format!("{} needs a value", flag))?;
+        match flag.as_str() {
+            "--user-id" => {
+                let id = value.parse().map_err(|_| format!("invalid user id: {}", value))?;
+                owner = Some(Owner::User(id));
+            }
+            "--format" => {
+                format = ExportFormat::parse(value).ok_or_else(|| format!("unknown format: {}", value))?;
+            }
+            "--from" => from = Some(parse_time(value)?),
+            "--to" => to = Some(parse_time(value)?),
+            "--output" => output = Some(value.clone()),
+            other => return Err(format!("unknown option: {}", other)),
+        }
+    }
+
+    let owner = owner.ok_or("either --user-id or --all is required")?;
+    if from.zip(to).is_some_and(|(from, to)| from >= to) {
+        return Err("--from must be before --to".to_string());
+    }
+
+    Ok(ExportArgs {
+        table,
+        query: ExportQuery { owner, from, to },
+        format,
+        output,
+    })
+}
+
+/// `analytics-service export ...`: write links or click events to a file or stdout.
+/// Unlike the HTTP API, this may export every user's data with `--all`.
+pub fn export(database_url: &str, args: &[String]) -> Result<(), String> {
+    let args = parse_args(args).map_err(|err| format!("{}\n\n{}", err, EXPORT_USAGE))?;
+
+    let mut conn = PgConnection::establish(database_url).map_err(|err| format!("failed to connect to DB: {}", err))?;
+    let out: Box<dyn Write + Send> = match &args.output {
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).map_err(|err| format!("failed to create {}: {}", path, err))?,
+        )),
+        None => Box::new(BufWriter::new(io::stdout())),
+    };
+
+    let (name, result) = match args.table {
+        Table::Links => ("links", write_export::<LinkRow, _>(&mut conn, &args.query, args.format, out)),
+        Table::Clicks => ("clicks", write_export::<ClickRow, _>(&mut conn, &args.query, args.format, out)),
+    };
+    let rows = result.map_err(|err| format!("export failed: {}", err))?;
+    eprintln!("✅ Exported {} {}", rows, name);
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use common::db::DbPool;
+use common::events::{ClickEvent, CLICK_EVENTS_QUEUE};
+use diesel::prelude::*;
+use futures_util::StreamExt;
+use lapin::{options::*, types::FieldTable, Channel, Consumer};
+use tracing::{info, warn};
+
+use crate::models::NewClickEvent;
+use crate::schema::click_events;
+
+/// Upper bound on how long a partially filled batch waits before it is written
+const BATCH_TIMEOUT: Duration = Duration::from_secs(1);
+/// Pause before redelivering a batch that could not be written
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Consume click events and write them to `click_events` in batches of up to `batch_size`.
+/// A batch is acknowledged only after its insert committed. Returns once the connection is lost;
+/// unacknowledged events are redelivered to the next session.
+pub async fn consume_click_events(channel: Channel, db_pool: DbPool, batch_size: usize) -> Result<(), lapin::Error> {
+    channel
+        .queue_declare(
+            CLICK_EVENTS_QUEUE,
+            QueueDeclareOptions {
+                durable: true,
+                ..QueueDeclareOptions::default()
+            },
+            FieldTable::default(),
+        )
+        .await?;
+    info!("✅ Queue declared: {}", CLICK_EVENTS_QUEUE);
+
+    // Let a full batch (plus the next one) be in flight at once
+    let prefetch = u16::try_from(batch_size * 2).unwrap_or(u16::MAX);
+    channel
+        .basic_qos(prefetch, BasicQosOptions::default())
+        .await?;
+
+    let mut consumer: Consumer = channel
+        .basic_consume(CLICK_EVENTS_QUEUE, "analytics_consumer", BasicConsumeOptions::default(), FieldTable::default())
+        .await?;
+
+    loop {
+        // Wait as long as it takes for the first event, then fill the batch for at most BATCH_TIMEOUT
+        let first = match consumer.next().await {
+            Some(delivery) => delivery?,
+            None => return Ok(()),
+        };
+
+        let mut last_tag = first.delivery_tag;
+        let mut rows: Vec<NewClickEvent> = Vec::with_capacity(batch_size);
+        push_event(&mut rows, &first.data);
+
+        let deadline = tokio::time::Instant::now() + BATCH_TIMEOUT;
+        while rows.len() < batch_size {
+            match tokio::time::timeout_at(deadline, consumer.next()).await {
+                Ok(Some(Ok(delivery))) => {
+                    last_tag = delivery.delivery_tag;
+                    push_event(&mut rows, &delivery.data);
+                }
+                _ => break,
+            }
+        }
+
+        let written = rows.len();
+        let pool = db_pool.clone();
+        let inserted = tokio::task::spawn_blocking(move || insert_events(&pool, &rows)).await;
+
+        // Acknowledge or requeue everything up to and including the last delivery of the batch
+        match inserted {
+            Ok(Ok(())) => {
+                if let Err(err) = channel.basic_ack(last_tag, BasicAckOptions { multiple: true }).await {
+                    warn!("⚠️ Failed to acknowledge click events: {}", err);
+                }
+                info!("📊 Stored {} click events", written);
+            }
+            Ok(Err(err)) => {
+                warn!("⚠️ Failed to store {} click events, requeueing: {}", written, err);
+                requeue(&channel, last_tag).await;
+            }
+            Err(err) => {
+                warn!("⚠️ Click event insert task failed, requeueing: {}", err);
+                requeue(&channel, last_tag).await;
+            }
+        }
+    }
+}
+
+/// Malformed messages are logged and skipped; they are acknowledged with the rest of the batch
+fn push_event(rows: &mut Vec<NewClickEvent>, data: &[u8]) {
+    match serde_json::from_slice::<ClickEvent>(data) {
+        Ok(event) => rows.push(NewClickEvent::from(event)),
+        Err(err) => warn!("⚠️ Ignoring malformed click event: {}", err),
+    }
+}
+
+fn insert_events(db_pool: &DbPool, rows: &[NewClickEvent]) -> Result<(), String> {
+    if rows.is_empty() {
+        return Ok(());
+    }
+    let mut conn = db_pool.get().map_err(|err| err.to_string())?;
+    diesel::insert_into(click_events::table)
+        .values(rows)
+        .execute(&mut conn)
+        .map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+async fn requeue(channel: &lapin::Channel, last_tag: u64) {
+    tokio::time::sleep(RETRY_DELAY).await;
+    let options = BasicNackOptions {
+        multiple: true,
+        requeue: true,
+    };
+    if let Err(err) = channel.basic_nack(last_tag, options).await {
+        warn!("⚠️ Failed to requeue click events: {}", err);
+    }
+}
@@ -0,0 +1,93 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use diesel::r2d2::PoolError;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use serde::Serialize;
+use tracing::error;
+
+/// Errors returned by analytics-service handlers.
+/// Each variant maps to one HTTP status and a stable machine-readable `error` code.
+#[derive(Debug)]
+pub enum AnalyticsError {
+    /// Malformed query parameters (time range, interval, ...)
+    InvalidRequest(String),
+    /// No link with the given short code or alias
+    LinkNotFound(String),
+    /// The link belongs to another user
+    Forbidden,
+    DatabaseUnavailable,
+    /// Redis, which holds the unique visitor estimates, cannot be reached
+    RedisUnavailable,
+    Internal(String),
+}
+
+/// JSON body sent with every error response
+#[derive(Serialize)]
+pub struct ErrorBody {
+    pub error: &'static str,
+    pub message: String,
+}
+
+impl AnalyticsError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AnalyticsError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            AnalyticsError::LinkNotFound(_) => StatusCode::NOT_FOUND,
+            AnalyticsError::Forbidden => StatusCode::FORBIDDEN,
+            AnalyticsError::DatabaseUnavailable | AnalyticsError::RedisUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            AnalyticsError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn body(&self) -> ErrorBody {
+        let (code, message) = match self {
+            AnalyticsError::InvalidRequest(msg) => ("invalid_request", msg.clone()),
+            AnalyticsError::LinkNotFound(code) => ("link_not_found", format!("No link found for short code: {}", code)),
+            AnalyticsError::Forbidden => ("forbidden", "You do not own this link".to_string()),
+            AnalyticsError::DatabaseUnavailable => ("database_unavailable", "Database is unavailable, try again later".to_string()),
+            AnalyticsError::RedisUnavailable => ("redis_unavailable", "Redis is unavailable, try again later".to_string()),
+            // Internal details are logged, not leaked to clients
+            AnalyticsError::Internal(_) => ("internal_error", "An internal error occurred".to_string()),
+        };
+        ErrorBody { error: code, message }
+    }
+}
+
+impl IntoResponse for AnalyticsError {
+    fn into_response(self) -> Response {
+        if let AnalyticsError::Internal(detail) = &self {
+            error!("🚨 Internal error: {}", detail);
+        }
+        (self.status(), Json(self.body())).into_response()
+    }
+}
+
+impl From<PoolError> for AnalyticsError {
+    fn from(err: PoolError) -> Self {
+        error!("🚨 Failed to get DB connection: {}", err);
+        AnalyticsError::DatabaseUnavailable
+    }
+}
+
+impl From<redis::RedisError> for AnalyticsError {
+    fn from(err: redis::RedisError) -> Self {
+        error!("🚨 Redis request failed: {}", err);
+        AnalyticsError::RedisUnavailable
+    }
+}
+
+impl From<DieselError> for AnalyticsError {
+    fn from(err: DieselError) -> Self {
+        match err {
+            DieselError::DatabaseError(DatabaseErrorKind::ClosedConnection, _)
+            | DieselError::BrokenTransactionManager => {
+                error!("🚨 Lost DB connection: {}", err);
+                AnalyticsError::DatabaseUnavailable
+            }
+            other => AnalyticsError::Internal(other.to_string()),
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use std::io::{self, Write};
+
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+mod rows;
+
+pub use rows::{ClickRow, ExportRow, LinkRow};
+
+/// Rows fetched per keyset page
+const PAGE_SIZE: i64 = 1000;
+/// Parquet rows buffered before a row group is written out
+const ROW_GROUP_ROWS: usize = 50_000;
+/// Bytes collected before a chunk is handed to the HTTP response
+const CHUNK_SIZE: usize = 64 * 1024;
+
+pub type ExportError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Jsonl,
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "csv" => Some(ExportFormat::Csv),
+            "jsonl" => Some(ExportFormat::Jsonl),
+            "parquet" => Some(ExportFormat::Parquet),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Jsonl => "application/x-ndjson",
+            ExportFormat::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+}
+
+/// Whose links (and clicks on them) are exported
+#[derive(Clone, Copy)]
+pub enum Owner {
+    User(i32),
+    /// Every link; only offered by the admin CLI
+    All,
+}
+
+/// Links are filtered by creation date, clicks by click time
+pub struct ExportQuery {
+    pub owner: Owner,
+    /// Inclusive
+    pub from: Option<NaiveDateTime>,
+    /// Exclusive
+    pub to: Option<NaiveDateTime>,
+}
+
+/// Writes rows in the requested format as they come, so memory use does not grow with the export
+enum Encoder<W: Write + Send> {
+    Csv(csv::Writer<W>),
+    Jsonl(W),
+    Parquet(ArrowWriter<W>),
+}
+
+impl<W: Write + Send> Encoder<W> {
+    fn new<R: ExportRow>(format: ExportFormat, out: W) -> Result<Self, ExportError> {
+        Ok(match format {
+            ExportFormat::Csv => Encoder::Csv(csv::Writer::from_writer(out)),
+            ExportFormat::Jsonl => Encoder::Jsonl(out),
+            ExportFormat::Parquet => {
+                let properties = WriterProperties::builder()
+                    .set_compression(Compression::SNAPPY)
+                    .set_max_row_group_size(ROW_GROUP_ROWS)
+                    .build();
+                Encoder::Parquet(ArrowWriter::try_new(out, R::arrow_schema(), Some(properties))?)
+            }
+        })
+    }
+
+    fn write<R: ExportRow>(&mut self, rows: &[R]) -> Result<(), ExportError> {
+        match self {
+            Encoder::Csv(writer) => {
+                for row in rows {
+                    writer.serialize(row)?;
+                }
+            }
+            Encoder::Jsonl(writer) => {
+                for row in rows {
+                    serde_json::to_writer(&mut *writer, row)?;
+                    writer.write_all(b"\n")?;
+                }
+            }
+            Encoder::Parquet(writer) => writer.write(&R::record_batch(rows)?)?,
+        }
+        Ok(())
+    }
+
+    /// Write out whatever is still buffered (and the Parquet footer) and hand back the output
+    fn finish(self) -> Result<W, ExportError> {
+        let mut out = match self {
+            Encoder::Csv(writer) => writer.into_inner().map_err(|err| err.into_error())?,
+            Encoder::Jsonl(writer) => writer,
+            Encoder::Parquet(writer) => writer.into_inner()?,
+        };
+        out.flush()?;
+        Ok(out)
+    }
+}
+
+/// Stream every row matching `query` to `out`, one keyset page at a time.
+/// Pages are read in one read-only snapshot, so the export is consistent even while clicks keep arriving.
+/// Returns the number of rows written.
+pub fn write_export<R: ExportRow, W: Write + Send>(
+    conn: &mut PgConnection,
+    query: &ExportQuery,
+    format: ExportFormat,
+    out: W,
+) -> Result<u64, ExportError> {
+    let mut encoder = Encoder::new::<R>(format, out)?;
+    let written = conn
+        .build_transaction()
+        .read_only()
+        .repeatable_read()
+        .run(|conn| -> Result<u64, ExportError> {
+            let mut cursor: Option<R::Cursor> = None;
+            let mut written = 0;
+            loop {
+                let page = R::fetch_page(conn, query, cursor.as_ref(), PAGE_SIZE)?;
+                let Some(last) = page.last() else { break };
+                cursor = Some(last.cursor());
+                encoder.write(&page)?;
+                written += page.len() as u64;
+                if (page.len() as i64) < PAGE_SIZE {
+                    break;
+                }
+            }
+            Ok(written)
+        })?;
+    encoder.finish()?;
+    Ok(written)
+}
+
+/// Blocking writer that hands its output to an async HTTP body in chunks.
+/// Fails with `BrokenPipe` once the receiver is gone, i.e. the client disconnected.
+pub struct ChannelWriter {
+    sender: mpsc::Sender<io::Result<Vec<u8>>>,
+    buffer: Vec<u8>,
+}
+
+impl ChannelWriter {
+    pub fn new(sender: mpsc::Sender<io::Result<Vec<u8>>>) -> Self {
+        ChannelWriter { sender, buffer: Vec::with_capacity(CHUNK_SIZE) }
+    }
+
+    fn send_buffer(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(&mut self.buffer, Vec::with_capacity(CHUNK_SIZE));
+        self.sender
+            .blocking_send(Ok(chunk))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "export receiver closed"))
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(data);
+        if self.buffer.len() >= CHUNK_SIZE {
+            self.send_buffer()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send_buffer()
+    }
+}
@@ -0,0 +1,225 @@
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, BooleanArray, Int32Array, Int64Array, RecordBatch, StringArray, TimestampMicrosecondArray};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use serde::Serialize;
+
+use super::{ExportQuery, Owner};
+use crate::schema::{click_events, url_mapping};
+
+/// A table that can be exported page by page
+pub trait ExportRow: Serialize + Sized {
+    /// Keyset position of a row; the next page starts right after it
+    type Cursor;
+
+    fn fetch_page(
+        conn: &mut PgConnection,
+        query: &ExportQuery,
+        after: Option<&Self::Cursor>,
+        limit: i64,
+    ) -> QueryResult<Vec<Self>>;
+
+    fn cursor(&self) -> Self::Cursor;
+
+    fn arrow_schema() -> SchemaRef;
+
+    fn record_batch(rows: &[Self]) -> Result<RecordBatch, ArrowError>;
+}
+
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(table_name = url_mapping)]
+pub struct LinkRow {
+    pub short_url: String,
+    pub alias: Option<String>,
+    pub long_url: String,
+    pub user_id: Option<i32>,
+    pub creation_date: NaiveDateTime,
+    pub expiration_date: Option<NaiveDateTime>,
+    pub click_count: i32,
+    pub redirect_type: i32,
+    pub disabled: bool,
+    pub deleted_at: Option<NaiveDateTime>,
+}
+
+#[derive(Queryable, Selectable, Serialize)]
+#[diesel(table_name = click_events)]
+pub struct ClickRow {
+    pub id: i64,
+    pub short_url: String,
+    pub clicked_at: NaiveDateTime,
+    pub referrer: Option<String>,
+    pub referrer_domain: Option<String>,
+    pub user_agent: Option<String>,
+    pub browser: Option<String>,
+    pub os: Option<String>,
+    pub country: Option<String>,
+    pub city: Option<String>,
+    pub traffic_class: String,
+    pub ip_hash: Option<String>,
+}
+
+/// Timestamps are stored as UTC without a zone, so they are exported as UTC
+fn timestamp_type() -> DataType {
+    DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+}
+
+fn strings<'a>(values: impl Iterator<Item = Option<&'a str>>) -> ArrayRef {
+    Arc::new(values.collect::<StringArray>())
+}
+
+fn timestamps(values: impl Iterator<Item = Option<NaiveDateTime>>) -> ArrayRef {
+    let micros: TimestampMicrosecondArray = values.map(|value| value.map(|v| v.and_utc().timestamp_micros())).collect();
+    Arc::new(micros.with_timezone("UTC"))
+}
+
+impl ExportRow for LinkRow {
+    type Cursor = String;
+
+    fn fetch_page(
+        conn: &mut PgConnection,
+        query: &ExportQuery,
+        after: Option<&String>,
+        limit: i64,
+    ) -> QueryResult<Vec<Self>> {
+        use crate::schema::url_mapping::dsl::*;
+
+        let mut page = url_mapping
+            .select(LinkRow::as_select())
+            .order(short_url)
+            .limit(limit)
+            .into_boxed();
+        if let Owner::User(owner) = query.owner {
+            page = page.filter(user_id.eq(owner));
+        }
+        if let Some(from) = query.from {
+            page = page.filter(creation_date.ge(from));
+        }
+        if let Some(to) = query.to {
+            page = page.filter(creation_date.lt(to));
+        }
+        if let Some(last) = after {
+            page = page.filter(short_url.gt(last));
+        }
+        page.load(conn)
+    }
+
+    fn cursor(&self) -> String {
+        self.short_url.clone()
+    }
+
+    fn arrow_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("short_url", DataType::Utf8, false),
+            Field::new("alias", DataType::Utf8, true),
+            Field::new("long_url", DataType::Utf8, false),
+            Field::new("user_id", DataType::Int32, true),
+            Field::new("creation_date", timestamp_type(), false),
+            Field::new("expiration_date", timestamp_type(), true),
+            Field::new("click_count", DataType::Int32, false),
+            Field::new("redirect_type", DataType::Int32, false),
+            Field::new("disabled", DataType::Boolean, false),
+            Field::new("deleted_at", timestamp_type(), true),
+        ]))
+    }
+
+    fn record_batch(rows: &[Self]) -> Result<RecordBatch, ArrowError> {
+        RecordBatch::try_new(
+            Self::arrow_schema(),
+            vec![
+                strings(rows.iter().map(|row| Some(row.short_url.as_str()))),
+                strings(rows.iter().map(|row| row.alias.as_deref())),
+                strings(rows.iter().map(|row| Some(row.long_url.as_str()))),
+                Arc::new(rows.iter().map(|row| row.user_id).collect::<Int32Array>()),
+                timestamps(rows.iter().map(|row| Some(row.creation_date))),
+                timestamps(rows.iter().map(|row| row.expiration_date)),
+                Arc::new(rows.iter().map(|row| Some(row.click_count)).collect::<Int32Array>()),
+                Arc::new(rows.iter().map(|row| Some(row.redirect_type)).collect::<Int32Array>()),
+                Arc::new(rows.iter().map(|row| Some(row.disabled)).collect::<BooleanArray>()),
+                timestamps(rows.iter().map(|row| row.deleted_at)),
+            ],
+        )
+    }
+}
+
+impl ExportRow for ClickRow {
+    type Cursor = (NaiveDateTime, i64);
+
+    /// Only raw clicks are exported; days past retention only exist as `click_daily` rollups
+    fn fetch_page(
+        conn: &mut PgConnection,
+        query: &ExportQuery,
+        after: Option<&(NaiveDateTime, i64)>,
+        limit: i64,
+    ) -> QueryResult<Vec<Self>> {
+        use crate::schema::click_events::dsl::*;
+
+        let mut page = click_events
+            .select(ClickRow::as_select())
+            .order((clicked_at, id))
+            .limit(limit)
+            .into_boxed();
+        if let Owner::User(owner) = query.owner {
+            let owned = url_mapping::table
+                .select(url_mapping::short_url)
+                .filter(url_mapping::user_id.eq(owner));
+            page = page.filter(short_url.eq_any(owned));
+        }
+        if let Some(from) = query.from {
+            page = page.filter(clicked_at.ge(from));
+        }
+        if let Some(to) = query.to {
+            page = page.filter(clicked_at.lt(to));
+        }
+        if let Some((last_clicked_at, last_id)) = after {
+            page = page.filter(
+                clicked_at
+                    .gt(last_clicked_at)
+                    .or(clicked_at.eq(last_clicked_at).and(id.gt(last_id))),
+            );
+        }
+        page.load(conn)
+    }
+
+    fn cursor(&self) -> (NaiveDateTime, i64) {
+        (self.clicked_at, self.id)
+    }
+
+    fn arrow_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("short_url", DataType::Utf8, false),
+            Field::new("clicked_at", timestamp_type(), false),
+            Field::new("referrer", DataType::Utf8, true),
+            Field::new("referrer_domain", DataType::Utf8, true),
+            Field::new("user_agent", DataType::Utf8, true),
+            Field::new("browser", DataType::Utf8, true),
+            Field::new("os", DataType::Utf8, true),
+            Field::new("country", DataType::Utf8, true),
+            Field::new("city", DataType::Utf8, true),
+            Field::new("traffic_class", DataType::Utf8, false),
+            Field::new("ip_hash", DataType::Utf8, true),
+        ]))
+    }
+
+    fn record_batch(rows: &[Self]) -> Result<RecordBatch, ArrowError> {
+        RecordBatch::try_new(
+            Self::arrow_schema(),
+            vec![
+                Arc::new(rows.iter().map(|row| Some(row.id)).collect::<Int64Array>()),
+                strings(rows.iter().map(|row| Some(row.short_url.as_str()))),
+                timestamps(rows.iter().map(|row| Some(row.clicked_at))),
+                strings(rows.iter().map(|row| row.referrer.as_deref())),
+                strings(rows.iter().map(|row| row.referrer_domain.as_deref())),
+                strings(rows.iter().map(|row| row.user_agent.as_deref())),
+                strings(rows.iter().map(|row| row.browser.as_deref())),
+                strings(rows.iter().map(|row| row.os.as_deref())),
+                strings(rows.iter().map(|row| row.country.as_deref())),
+                strings(rows.iter().map(|row| row.city.as_deref())),
+                strings(rows.iter().map(|row| Some(row.traffic_class.as_str()))),
+                strings(rows.iter().map(|row| row.ip_hash.as_deref())),
+            ],
+        )
+    }
+}
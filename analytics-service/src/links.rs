@@ -0,0 +1,21 @@
+use diesel::prelude::*;
+
+use crate::error::AnalyticsError;
+use crate::schema::url_mapping::dsl::*;
+
+/// Resolve a short code or alias to the link's short code, making sure it belongs to `owner`.
+/// Soft-deleted links are treated as missing, like in shortener-service.
+pub fn find_owned_code(conn: &mut PgConnection, code: &str, owner: i32) -> Result<String, AnalyticsError> {
+    let (link_code, link_owner) = url_mapping
+        .filter(short_url.eq(code).or(alias.eq(code)))
+        .filter(deleted_at.is_null())
+        .select((short_url, user_id))
+        .first::<(String, Option<i32>)>(conn)
+        .optional()?
+        .ok_or_else(|| AnalyticsError::LinkNotFound(code.to_string()))?;
+
+    if link_owner != Some(owner) {
+        return Err(AnalyticsError::Forbidden);
+    }
+    Ok(link_code)
+}
@@ -0,0 +1,101 @@
+use axum::{middleware, routing::get, Router};
+use diesel::prelude::*;
+use diesel::PgConnection;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tracing::info;
+use common::logging::init_tracing;
+use common::db::{init_pool, run_analytics_migrations, DbPool};
+use common::auth::{require_service_token, ServiceToken};
+use common::rabbitmq::AmqpManager;
+
+mod cli;
+mod consumer;
+mod error;
+mod export;
+mod links;
+mod models;
+mod partitions;
+mod rollup;
+mod routes;
+mod schema;
+mod useragent;
+
+pub struct AppState {
+    pub db_pool: DbPool,
+    /// Holds the per-day unique visitor HyperLogLogs written by redirect-service
+    pub redis: redis::Client,
+    pub amqp: Arc<AmqpManager>,
+}
+
+#[tokio::main]
+async fn main() {
+    dotenvy::dotenv().ok();
+
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+
+    // Admin CLI; runs before tracing is set up so log lines never mix into an export on stdout
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(command) = args.first() {
+        if command != "export" {
+            eprintln!("unknown command: {}\n\n{}", command, cli::EXPORT_USAGE);
+            std::process::exit(2);
+        }
+        if let Err(err) = cli::export(&database_url, &args[1..]) {
+            eprintln!("❌ {}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    init_tracing();
+    let rabbitmq_url = std::env::var("RABBITMQ_URL").expect("RABBITMQ_URL must be set");
+    let redis_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
+    // Largest number of click events written in one insert
+    let batch_size = std::env::var("CLICK_BATCH_SIZE")
+        .ok()
+        .and_then(|size| size.parse().ok())
+        .filter(|size| (1..=5000).contains(size))
+        .unwrap_or(500);
+    // Raw clicks older than this are removed once rolled up into click_daily
+    let retention_days = std::env::var("CLICK_RETENTION_DAYS")
+        .ok()
+        .and_then(|days| days.parse().ok())
+        .filter(|days| *days >= 1)
+        .unwrap_or(90);
+
+    let service_token = ServiceToken::from_env();
+
+    let mut conn = PgConnection::establish(&database_url).expect("Failed to connect to DB");
+    run_analytics_migrations(&mut conn);
+    info!("✅ Analytics migrations completed");
+
+    let db_pool = init_pool(&database_url);
+    partitions::ensure_upcoming(&db_pool);
+    partitions::spawn_maintenance(db_pool.clone());
+    rollup::spawn_rollup(db_pool.clone(), retention_days);
+
+    // Connects in the background; stats and exports are served while RabbitMQ is down
+    let amqp = AmqpManager::spawn(rabbitmq_url);
+    let consumer_pool = db_pool.clone();
+    amqp.run_consumer("Click event", move |channel| {
+        consumer::consume_click_events(channel, consumer_pool.clone(), batch_size)
+    });
+
+    let redis = redis::Client::open(redis_url).expect("Invalid REDIS_URL");
+    let state = Arc::new(AppState { db_pool, redis, amqp });
+    let app = Router::new()
+        .route("/links/:code/stats", get(routes::stats::link_stats))
+        .route("/links/:code/uniques", get(routes::uniques::unique_visitors))
+        .route("/exports/links", get(routes::export::export_links))
+        .route("/exports/clicks", get(routes::export::export_clicks))
+        // The routes above answer for the `user_id` the gateway passes along, so only the gateway may call them
+        .route_layer(middleware::from_fn_with_state(service_token, require_service_token))
+        .route("/health", get(routes::health::health))
+        .with_state(state);
+
+    let listener = TcpListener::bind("0.0.0.0:8083").await.unwrap();
+    println!("🚀 Server running on http://0.0.0.0:8083");
+
+    axum::serve(listener, app).await.unwrap();
+}
@@ -0,0 +1,55 @@
+use chrono::NaiveDateTime;
+use common::events::ClickEvent;
+use diesel::Insertable;
+use url::Url;
+
+use crate::schema::click_events;
+use crate::useragent;
+
+#[derive(Insertable)]
+#[diesel(table_name = click_events)]
+pub struct NewClickEvent {
+    pub short_url: String,
+    pub clicked_at: NaiveDateTime,
+    pub referrer: Option<String>,
+    pub user_agent: Option<String>,
+    pub accept_language: Option<String>,
+    pub ip_hash: Option<String>,
+    pub referrer_domain: Option<String>,
+    pub browser: Option<String>,
+    pub os: Option<String>,
+    pub country: Option<String>,
+    pub city: Option<String>,
+    pub traffic_class: String,
+}
+
+impl From<ClickEvent> for NewClickEvent {
+    fn from(event: ClickEvent) -> Self {
+        let device = event.user_agent.as_deref().map(useragent::parse);
+        let referrer_domain = event
+            .referrer
+            .as_deref()
+            .and_then(|referrer| Url::parse(referrer).ok())
+            .and_then(|referrer| referrer.host_str().map(|host| host.trim_start_matches("www.").to_ascii_lowercase()))
+            .filter(|domain| domain.len() <= 255);
+        let (browser, os) = match device {
+            Some(device) => (device.browser, device.os),
+            None => (None, None),
+        };
+
+        NewClickEvent {
+            short_url: event.short_url,
+            clicked_at: event.clicked_at.naive_utc(),
+            referrer: event.referrer,
+            user_agent: event.user_agent,
+            accept_language: event.accept_language,
+            ip_hash: event.ip_hash,
+            referrer_domain,
+            browser,
+            os,
+            country: event.country.filter(|country| country.len() == 2),
+            city: event.city.filter(|city| city.len() <= 128),
+            traffic_class: event.traffic_class.as_str().to_string(),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use chrono::{Months, Utc};
+use common::db::{ensure_click_events_partition, DbPool};
+use tracing::warn;
+
+/// How often upcoming monthly partitions are checked for
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Make sure partitions exist for this month and the next, so new clicks never
+/// fall into the default partition (which would block creating that month later)
+pub fn ensure_upcoming(db_pool: &DbPool) {
+    let mut conn = match db_pool.get() {
+        Ok(conn) => conn,
+        Err(err) => {
+            warn!("⚠️ Failed to get a connection for partition maintenance: {}", err);
+            return;
+        }
+    };
+
+    let today = Utc::now().date_naive();
+    let next_month = today + Months::new(1);
+    for month in [today, next_month] {
+        if let Err(err) = ensure_click_events_partition(&mut conn, month) {
+            warn!("⚠️ Failed to create click_events partition for {}: {}", month.format("%Y-%m"), err);
+        }
+    }
+}
+
+pub fn spawn_maintenance(db_pool: DbPool) {
+    tokio::spawn(async move {
+        // `ensure_upcoming` already ran at startup, so the first check is one interval away
+        let start = tokio::time::Instant::now() + MAINTENANCE_INTERVAL;
+        let mut ticker = tokio::time::interval_at(start, MAINTENANCE_INTERVAL);
+        loop {
+            ticker.tick().await;
+            let db_pool = db_pool.clone();
+            let _ = tokio::task::spawn_blocking(move || ensure_upcoming(&db_pool)).await;
+        }
+    });
+}
@@ -0,0 +1,254 @@
+use std::time::Duration;
+
+use chrono::{Days, NaiveDate, Utc};
+use common::db::{drop_click_events_partitions_before, DbPool};
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Bool, Date, Nullable, Timestamp};
+use tracing::{info, warn};
+
+/// How often new days are rolled up and old raw clicks removed
+const ROLLUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Days rolled up again on every run, so events that arrive late still make it into `click_daily`
+const REROLL_DAYS: u64 = 2;
+/// Values kept per breakdown and day
+const ROLLUP_TOP_ENTRIES: i64 = 50;
+/// Keeps replicas from rolling up the same days at once
+const ROLLUP_LOCK_KEY: i64 = 0x636c_6963_6b5f_726f; // "click_ro"
+
+/// A breakdown kept for every day: the raw column it groups by, the `click_daily` column
+/// holding its top values, and the label used when the raw value is missing
+pub struct Dimension {
+    pub column: &'static str,
+    pub rollup_column: &'static str,
+    pub missing: &'static str,
+}
+
+impl Dimension {
+    /// Table function yielding `(key, value)` pairs from a `click_daily` row aliased as `daily`
+    pub fn rollup_entries(&self) -> String {
+        format!("jsonb_each_text(daily.{})", self.rollup_column)
+    }
+}
+
+pub const REFERRERS: Dimension = Dimension { column: "referrer_domain", rollup_column: "top_referrers", missing: "direct" };
+pub const BROWSERS: Dimension = Dimension { column: "browser", rollup_column: "browsers", missing: "unknown" };
+pub const OPERATING_SYSTEMS: Dimension = Dimension { column: "os", rollup_column: "operating_systems", missing: "unknown" };
+pub const COUNTRIES: Dimension = Dimension { column: "country", rollup_column: "countries", missing: "unknown" };
+pub const CITIES: Dimension = Dimension { column: "city", rollup_column: "cities", missing: "unknown" };
+
+const DIMENSIONS: [Dimension; 5] = [REFERRERS, BROWSERS, OPERATING_SYSTEMS, COUNTRIES, CITIES];
+
+#[derive(QueryableByName)]
+struct RollupState {
+    /// Every day before this one is in `click_daily`
+    #[diesel(sql_type = Nullable<Date>)]
+    rolled_up_until: Option<NaiveDate>,
+    /// Raw clicks before this day have been removed
+    #[diesel(sql_type = Nullable<Date>)]
+    raw_since: Option<NaiveDate>,
+}
+
+#[derive(QueryableByName)]
+struct FirstClick {
+    #[diesel(sql_type = Nullable<Date>)]
+    day: Option<NaiveDate>,
+}
+
+#[derive(QueryableByName)]
+struct Locked {
+    #[diesel(sql_type = Bool)]
+    locked: bool,
+}
+
+fn load_state(conn: &mut PgConnection) -> QueryResult<RollupState> {
+    diesel::sql_query("SELECT rolled_up_until, raw_since FROM click_rollup_state").get_result(conn)
+}
+
+/// First day whose raw clicks are still kept, or `None` while nothing has been removed.
+/// Stats read `click_daily` for the days before it and `click_events` from it on.
+pub fn raw_since(conn: &mut PgConnection) -> QueryResult<Option<NaiveDate>> {
+    Ok(load_state(conn)?.raw_since)
+}
+
+/// Start of `day` as a naive UTC timestamp
+fn midnight(day: NaiveDate) -> chrono::NaiveDateTime {
+    day.and_hms_opt(0, 0, 0).expect("midnight exists every day")
+}
+
+/// `(short_url, traffic_class, entries)` with the day's most frequent values of `dimension` as a JSON object
+fn breakdown_sql(dimension: &Dimension) -> String {
+    format!(
+        "SELECT short_url, traffic_class, jsonb_object_agg(key, clicks) AS entries \
+         FROM ( \
+             SELECT short_url, traffic_class, key, clicks, \
+                    row_number() OVER (PARTITION BY short_url, traffic_class ORDER BY clicks DESC, key) AS rank \
+             FROM ( \
+                 SELECT short_url, traffic_class, COALESCE({column}, '{missing}') AS key, COUNT(*) AS clicks \
+                 FROM click_events \
+                 WHERE clicked_at >= $2 AND clicked_at < $3 \
+                 GROUP BY 1, 2, 3 \
+             ) AS counts \
+         ) AS ranked \
+         WHERE rank <= {top} \
+         GROUP BY 1, 2",
+        column = dimension.column,
+        missing = dimension.missing,
+        top = ROLLUP_TOP_ENTRIES,
+    )
+}
+
+/// Replace the `click_daily` rows of `day` with fresh aggregates of its raw clicks
+fn roll_up_day(conn: &mut PgConnection, day: NaiveDate) -> QueryResult<usize> {
+    let columns: Vec<&str> = DIMENSIONS.iter().map(|dimension| dimension.rollup_column).collect();
+    let selected: Vec<String> = DIMENSIONS
+        .iter()
+        .map(|dimension| format!("COALESCE({0}.entries, '{{}}')", dimension.rollup_column))
+        .collect();
+    let joins: Vec<String> = DIMENSIONS
+        .iter()
+        .map(|dimension| {
+            format!(
+                "LEFT JOIN ({}) AS {} USING (short_url, traffic_class)",
+                breakdown_sql(dimension),
+                dimension.rollup_column
+            )
+        })
+        .collect();
+
+    let insert_sql = format!(
+        "INSERT INTO click_daily (short_code, day, traffic_class, clicks, uniques, {columns}) \
+         SELECT totals.short_url, $1, totals.traffic_class, totals.clicks, totals.uniques, {selected} \
+         FROM ( \
+             SELECT short_url, traffic_class, COUNT(*) AS clicks, COUNT(DISTINCT ip_hash) AS uniques \
+             FROM click_events \
+             WHERE clicked_at >= $2 AND clicked_at < $3 \
+             GROUP BY 1, 2 \
+         ) AS totals \
+         {joins}",
+        columns = columns.join(", "),
+        selected = selected.join(", "),
+        joins = joins.join(" "),
+    );
+
+    let next_day = day + Days::new(1);
+    conn.transaction(|conn| {
+        diesel::sql_query("DELETE FROM click_daily WHERE day = $1")
+            .bind::<Date, _>(day)
+            .execute(conn)?;
+        let rows = diesel::sql_query(insert_sql)
+            .bind::<Date, _>(day)
+            .bind::<Timestamp, _>(midnight(day))
+            .bind::<Timestamp, _>(midnight(next_day))
+            .execute(conn)?;
+        diesel::sql_query(
+            "UPDATE click_rollup_state SET rolled_up_until = GREATEST(COALESCE(rolled_up_until, $1), $1)",
+        )
+        .bind::<Date, _>(next_day)
+        .execute(conn)?;
+        Ok(rows)
+    })
+}
+
+/// Roll up every complete day not rolled up yet, plus the last few again
+fn roll_up(conn: &mut PgConnection, today: NaiveDate) -> QueryResult<usize> {
+    let state = load_state(conn)?;
+    let first = match state.rolled_up_until {
+        // Raw rows before `raw_since` are gone, so those days must keep their rollups
+        Some(until) => (until - Days::new(REROLL_DAYS)).max(state.raw_since.unwrap_or(NaiveDate::MIN)),
+        None => {
+            let first_click: FirstClick =
+                diesel::sql_query("SELECT MIN(clicked_at)::date AS day FROM click_events").get_result(conn)?;
+            match first_click.day {
+                Some(day) => day,
+                None => return Ok(0),
+            }
+        }
+    };
+
+    let mut days = 0;
+    for day in first.iter_days().take_while(|day| *day < today) {
+        roll_up_day(conn, day)?;
+        days += 1;
+    }
+    Ok(days)
+}
+
+/// Remove raw clicks older than `retention_days`, but never any that are not rolled up yet.
+/// Whole monthly partitions are dropped; the rest is deleted row by row.
+fn enforce_retention(conn: &mut PgConnection, today: NaiveDate, retention_days: u64) -> QueryResult<Option<NaiveDate>> {
+    let state = load_state(conn)?;
+    let Some(rolled_up_until) = state.rolled_up_until else {
+        return Ok(None);
+    };
+    let cutoff = (today - Days::new(retention_days)).min(rolled_up_until);
+    if state.raw_since.is_some_and(|since| since >= cutoff) {
+        return Ok(None);
+    }
+
+    conn.transaction(|conn| {
+        for partition in drop_click_events_partitions_before(conn, cutoff)? {
+            info!("🗑️ Dropped click_events partition {}", partition);
+        }
+        diesel::sql_query("DELETE FROM click_events WHERE clicked_at < $1")
+            .bind::<Timestamp, _>(midnight(cutoff))
+            .execute(conn)?;
+        diesel::sql_query("UPDATE click_rollup_state SET raw_since = $1")
+            .bind::<Date, _>(cutoff)
+            .execute(conn)?;
+        Ok(Some(cutoff))
+    })
+}
+
+/// One rollup and retention pass. Skipped when another replica is already running one.
+pub fn run_once(db_pool: &DbPool, retention_days: u64) {
+    let mut conn = match db_pool.get() {
+        Ok(conn) => conn,
+        Err(err) => {
+            warn!("⚠️ Failed to get a connection for the click rollup: {}", err);
+            return;
+        }
+    };
+
+    let lock = diesel::sql_query("SELECT pg_try_advisory_lock($1) AS locked")
+        .bind::<BigInt, _>(ROLLUP_LOCK_KEY)
+        .get_result::<Locked>(&mut conn);
+    match lock {
+        Ok(Locked { locked: true }) => {}
+        Ok(Locked { locked: false }) => return,
+        Err(err) => {
+            warn!("⚠️ Failed to take the click rollup lock: {}", err);
+            return;
+        }
+    }
+
+    let today = Utc::now().date_naive();
+    match roll_up(&mut conn, today) {
+        Ok(0) => {}
+        Ok(days) => info!("📊 Rolled up clicks for {} days", days),
+        Err(err) => warn!("⚠️ Failed to roll up clicks, will retry: {}", err),
+    }
+    match enforce_retention(&mut conn, today, retention_days) {
+        Ok(None) => {}
+        Ok(Some(cutoff)) => info!("🗑️ Removed raw clicks before {}", cutoff),
+        Err(err) => warn!("⚠️ Failed to remove old raw clicks, will retry: {}", err),
+    }
+
+    if let Err(err) = diesel::sql_query("SELECT pg_advisory_unlock($1)")
+        .bind::<BigInt, _>(ROLLUP_LOCK_KEY)
+        .execute(&mut conn)
+    {
+        warn!("⚠️ Failed to release the click rollup lock: {}", err);
+    }
+}
+
+/// Run the rollup every `ROLLUP_INTERVAL`, starting right away
+pub fn spawn_rollup(db_pool: DbPool, retention_days: u64) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(ROLLUP_INTERVAL);
+        loop {
+            ticker.tick().await;
+            let db_pool = db_pool.clone();
+            let _ = tokio::task::spawn_blocking(move || run_once(&db_pool, retention_days)).await;
+        }
+    });
+}
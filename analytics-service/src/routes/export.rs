@@ -0,0 +1,91 @@
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::AppState;
+use crate::error::AnalyticsError;
+use crate::export::{write_export, ChannelWriter, ClickRow, ExportFormat, ExportQuery, ExportRow, LinkRow, Owner};
+
+/// Chunks buffered between the export thread and a slow client
+const STREAM_CHUNKS: usize = 4;
+
+#[derive(Deserialize)]
+pub struct ExportParams {
+    /// Owner authenticated by the gateway; only trusted because the route requires the service token
+    user_id: i32,
+    #[serde(default)]
+    format: ExportFormat,
+    /// Inclusive; links are filtered by creation time, clicks by click time
+    from: Option<DateTime<Utc>>,
+    /// Exclusive
+    to: Option<DateTime<Utc>>,
+}
+
+/// Stream the export from a blocking thread. Errors after the first byte can no longer change
+/// the status code, so they abort the response and the client sees a truncated body.
+fn stream_export<R: ExportRow + Send + 'static>(
+    state: &AppState,
+    params: ExportParams,
+    name: &'static str,
+) -> Result<Response, AnalyticsError> {
+    if params.from.zip(params.to).is_some_and(|(from, to)| from >= to) {
+        return Err(AnalyticsError::InvalidRequest("from must be before to".to_string()));
+    }
+    let query = ExportQuery {
+        owner: Owner::User(params.user_id),
+        from: params.from.map(|from| from.naive_utc()),
+        to: params.to.map(|to| to.naive_utc()),
+    };
+    let format = params.format;
+
+    // Taken up front so an unavailable database is still reported with a proper status
+    let mut conn = state.db_pool.get()?;
+
+    let (sender, mut receiver) = mpsc::channel(STREAM_CHUNKS);
+    tokio::task::spawn_blocking(move || {
+        match write_export::<R, _>(&mut conn, &query, format, ChannelWriter::new(sender.clone())) {
+            Ok(rows) => info!("📦 Exported {} {} for user {}", rows, name, params.user_id),
+            Err(err) => {
+                warn!("⚠️ Export of {} for user {} failed: {}", name, params.user_id, err);
+                let _ = sender.blocking_send(Err(std::io::Error::other(err.to_string())));
+            }
+        }
+    });
+
+    let body = Body::from_stream(futures_util::stream::poll_fn(move |cx| receiver.poll_recv(cx)));
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.{}\"", name, format.extension()),
+            ),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// The caller's links, including deleted and disabled ones.
+pub async fn export_links(
+    Query(params): Query<ExportParams>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, AnalyticsError> {
+    stream_export::<LinkRow>(&state, params, "links")
+}
+
+/// Raw click events on the caller's links.
+pub async fn export_clicks(
+    Query(params): Query<ExportParams>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Response, AnalyticsError> {
+    stream_export::<ClickRow>(&state, params, "clicks")
+}
@@ -0,0 +1,10 @@
+use axum::{extract::State, Json};
+use common::rabbitmq::Health;
+use std::sync::Arc;
+
+use crate::AppState;
+
+/// Liveness plus the RabbitMQ connection state
+pub async fn health(State(state): State<Arc<AppState>>) -> Json<Health> {
+    Json(state.amqp.health())
+}
@@ -0,0 +1,4 @@
+pub mod export;
+pub mod health;
+pub mod stats;
+pub mod uniques;
@@ -0,0 +1,316 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::{DateTime, Duration, NaiveDateTime, NaiveTime, Utc};
+use diesel::prelude::*;
+use diesel::sql_types::{BigInt, Text, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::AppState;
+use crate::error::AnalyticsError;
+use crate::links::find_owned_code;
+use crate::rollup::{self, Dimension, BROWSERS, CITIES, COUNTRIES, OPERATING_SYSTEMS, REFERRERS};
+
+/// Range used when the caller does not pass `from`
+const DEFAULT_RANGE_DAYS: i64 = 7;
+/// Longest time series a single request may ask for
+const MAX_BUCKETS: i64 = 2000;
+/// Entries returned per breakdown
+const TOP_ENTRIES: i64 = 10;
+/// Clicks per traffic class; rollups keep one row per class, so they need no JSON breakdown
+const TRAFFIC: Dimension = Dimension { column: "traffic_class", rollup_column: "traffic_class", missing: "unknown" };
+const TRAFFIC_ROLLUP_ENTRIES: &str = "(VALUES (daily.traffic_class::text, daily.clicks::text))";
+
+#[derive(Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Interval {
+    Hour,
+    #[default]
+    Day,
+}
+
+impl Interval {
+    /// Unit name understood by Postgres `date_trunc` and interval literals
+    pub fn unit(self) -> &'static str {
+        match self {
+            Interval::Hour => "hour",
+            Interval::Day => "day",
+        }
+    }
+
+    pub fn duration(self) -> Duration {
+        match self {
+            Interval::Hour => Duration::hours(1),
+            Interval::Day => Duration::days(1),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct StatsParams {
+    user_id: i32,
+    /// Start of the range, inclusive; defaults to 7 days before `to`
+    from: Option<DateTime<Utc>>,
+    /// End of the range, exclusive; defaults to now
+    to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    interval: Interval,
+    /// Which traffic to count; humans only unless asked otherwise
+    #[serde(default)]
+    class: ClassFilter,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClassFilter {
+    #[default]
+    Human,
+    Bot,
+    Preview,
+    All,
+}
+
+impl ClassFilter {
+    fn as_str(self) -> &'static str {
+        match self {
+            ClassFilter::Human => "human",
+            ClassFilter::Bot => "bot",
+            ClassFilter::Preview => "preview",
+            ClassFilter::All => "all",
+        }
+    }
+}
+
+#[derive(Serialize, QueryableByName)]
+pub struct SeriesPoint {
+    #[diesel(sql_type = Timestamp)]
+    #[serde(serialize_with = "serialize_utc")]
+    pub bucket: NaiveDateTime,
+    #[diesel(sql_type = BigInt)]
+    pub clicks: i64,
+    #[diesel(sql_type = BigInt)]
+    pub unique_visitors: i64,
+}
+
+#[derive(Serialize, QueryableByName)]
+pub struct BreakdownEntry {
+    #[diesel(sql_type = Text)]
+    pub key: String,
+    #[diesel(sql_type = BigInt)]
+    pub clicks: i64,
+}
+
+#[derive(QueryableByName)]
+struct Totals {
+    #[diesel(sql_type = BigInt)]
+    clicks: i64,
+    #[diesel(sql_type = BigInt)]
+    unique_visitors: i64,
+}
+
+#[derive(Serialize)]
+pub struct LinkStatsResponse {
+    pub short_code: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub interval: Interval,
+    pub class: ClassFilter,
+    pub total_clicks: i64,
+    /// Distinct visitor hashes over the whole range. Days whose raw clicks are past retention
+    /// only add their daily count, so visitors returning on several of those days count more than once.
+    pub unique_visitors: i64,
+    pub series: Vec<SeriesPoint>,
+    pub referrers: Vec<BreakdownEntry>,
+    pub browsers: Vec<BreakdownEntry>,
+    pub operating_systems: Vec<BreakdownEntry>,
+    pub countries: Vec<BreakdownEntry>,
+    pub cities: Vec<BreakdownEntry>,
+    /// Clicks per traffic class (human, bot, preview), regardless of `class`
+    pub traffic: Vec<BreakdownEntry>,
+}
+
+/// Rows a stats query looks at: one link, a time range and a traffic class.
+/// Clicks before `raw_since` are read from the `click_daily` rollups, whole days at a time.
+pub struct ClickFilter<'a> {
+    pub code: &'a str,
+    pub from: NaiveDateTime,
+    pub to: NaiveDateTime,
+    pub class: ClassFilter,
+    pub raw_since: NaiveDateTime,
+}
+
+fn serialize_utc<S: serde::Serializer>(value: &NaiveDateTime, serializer: S) -> Result<S::Ok, S::Error> {
+    value.and_utc().serialize(serializer)
+}
+
+/// Validate and fill in the requested time range
+pub fn resolve_range(
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    interval: Interval,
+) -> Result<(DateTime<Utc>, DateTime<Utc>), AnalyticsError> {
+    let to = to.unwrap_or_else(Utc::now);
+    let from = from.unwrap_or(to - Duration::days(DEFAULT_RANGE_DAYS));
+
+    if from >= to {
+        return Err(AnalyticsError::InvalidRequest("from must be before to".to_string()));
+    }
+    if (to - from).num_seconds() / interval.duration().num_seconds() > MAX_BUCKETS {
+        return Err(AnalyticsError::InvalidRequest(format!(
+            "Range is too long for the {} interval; at most {} buckets are returned",
+            interval.unit(),
+            MAX_BUCKETS
+        )));
+    }
+    Ok((from, to))
+}
+
+/// Clicks per bucket, with empty buckets filled in as zero.
+/// Rolled-up days land in the bucket of their midnight, whatever the interval.
+fn click_series(conn: &mut PgConnection, filter: &ClickFilter, interval: Interval) -> QueryResult<Vec<SeriesPoint>> {
+    diesel::sql_query(
+        "SELECT buckets.bucket, \
+                COALESCE(SUM(counts.clicks), 0)::bigint AS clicks, \
+                COALESCE(SUM(counts.unique_visitors), 0)::bigint AS unique_visitors \
+         FROM generate_series(date_trunc($1, $3), $4 - interval '1 microsecond', ('1 ' || $1)::interval) AS buckets(bucket) \
+         LEFT JOIN ( \
+             SELECT date_trunc($1, clicked_at) AS bucket, COUNT(*) AS clicks, COUNT(DISTINCT ip_hash) AS unique_visitors \
+             FROM click_events \
+             WHERE short_url = $2 AND clicked_at >= GREATEST($3, $6) AND clicked_at < $4 \
+               AND ($5 = 'all' OR traffic_class = $5) \
+             GROUP BY 1 \
+             UNION ALL \
+             SELECT date_trunc($1, day::timestamp), clicks, uniques \
+             FROM click_daily \
+             WHERE short_code = $2 AND day >= $3::date AND day < $6::date AND day < $4 \
+               AND ($5 = 'all' OR traffic_class = $5) \
+         ) AS counts USING (bucket) \
+         GROUP BY buckets.bucket \
+         ORDER BY buckets.bucket",
+    )
+    .bind::<Text, _>(interval.unit())
+    .bind::<Text, _>(filter.code)
+    .bind::<Timestamp, _>(filter.from)
+    .bind::<Timestamp, _>(filter.to)
+    .bind::<Text, _>(filter.class.as_str())
+    .bind::<Timestamp, _>(filter.raw_since)
+    .load(conn)
+}
+
+fn click_totals(conn: &mut PgConnection, filter: &ClickFilter) -> QueryResult<Totals> {
+    diesel::sql_query(
+        "SELECT COALESCE(SUM(clicks), 0)::bigint AS clicks, COALESCE(SUM(unique_visitors), 0)::bigint AS unique_visitors \
+         FROM ( \
+             SELECT COUNT(*) AS clicks, COUNT(DISTINCT ip_hash) AS unique_visitors \
+             FROM click_events \
+             WHERE short_url = $1 AND clicked_at >= GREATEST($2, $5) AND clicked_at < $3 \
+               AND ($4 = 'all' OR traffic_class = $4) \
+             UNION ALL \
+             SELECT SUM(clicks), SUM(uniques) \
+             FROM click_daily \
+             WHERE short_code = $1 AND day >= $2::date AND day < $5::date AND day < $3 \
+               AND ($4 = 'all' OR traffic_class = $4) \
+         ) AS parts",
+    )
+    .bind::<Text, _>(filter.code)
+    .bind::<Timestamp, _>(filter.from)
+    .bind::<Timestamp, _>(filter.to)
+    .bind::<Text, _>(filter.class.as_str())
+    .bind::<Timestamp, _>(filter.raw_since)
+    .get_result(conn)
+}
+
+/// Most frequent values of a dimension; `rollup_entries` yields the `(key, value)` pairs of a rolled-up day
+fn top_values(
+    conn: &mut PgConnection,
+    filter: &ClickFilter,
+    dimension: &Dimension,
+    rollup_entries: &str,
+) -> QueryResult<Vec<BreakdownEntry>> {
+    diesel::sql_query(format!(
+        "SELECT key, SUM(clicks)::bigint AS clicks \
+         FROM ( \
+             SELECT COALESCE({column}, $1) AS key, COUNT(*) AS clicks \
+             FROM click_events \
+             WHERE short_url = $2 AND clicked_at >= GREATEST($3, $7) AND clicked_at < $4 \
+               AND ($5 = 'all' OR traffic_class = $5) \
+             GROUP BY 1 \
+             UNION ALL \
+             SELECT entries.key, entries.value::bigint \
+             FROM click_daily AS daily \
+             CROSS JOIN LATERAL {rollup_entries} AS entries(key, value) \
+             WHERE daily.short_code = $2 AND daily.day >= $3::date AND daily.day < $7::date AND daily.day < $4 \
+               AND ($5 = 'all' OR daily.traffic_class = $5) \
+         ) AS parts \
+         GROUP BY key \
+         ORDER BY clicks DESC, key \
+         LIMIT $6",
+        column = dimension.column,
+    ))
+    .bind::<Text, _>(dimension.missing)
+    .bind::<Text, _>(filter.code)
+    .bind::<Timestamp, _>(filter.from)
+    .bind::<Timestamp, _>(filter.to)
+    .bind::<Text, _>(filter.class.as_str())
+    .bind::<BigInt, _>(TOP_ENTRIES)
+    .bind::<Timestamp, _>(filter.raw_since)
+    .load(conn)
+}
+
+fn breakdown(conn: &mut PgConnection, filter: &ClickFilter, dimension: &Dimension) -> QueryResult<Vec<BreakdownEntry>> {
+    top_values(conn, filter, dimension, &dimension.rollup_entries())
+}
+
+/// Click time series and breakdowns for one link, visible only to its owner.
+pub async fn link_stats(
+    Path(code): Path<String>,
+    Query(params): Query<StatsParams>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<LinkStatsResponse>, AnalyticsError> {
+    let (from, to) = resolve_range(params.from, params.to, params.interval)?;
+
+    let mut conn = state.db_pool.get()?;
+    let short_code = find_owned_code(&mut conn, &code, params.user_id)?;
+    // Until retention removes anything, every click is still in click_events
+    let raw_since = rollup::raw_since(&mut conn)?.unwrap_or(DateTime::UNIX_EPOCH.date_naive());
+
+    let filter = ClickFilter {
+        code: &short_code,
+        from: from.naive_utc(),
+        to: to.naive_utc(),
+        class: params.class,
+        raw_since: raw_since.and_time(NaiveTime::MIN),
+    };
+    let totals = click_totals(&mut conn, &filter)?;
+    let series = click_series(&mut conn, &filter, params.interval)?;
+    let referrers = breakdown(&mut conn, &filter, &REFERRERS)?;
+    let browsers = breakdown(&mut conn, &filter, &BROWSERS)?;
+    let operating_systems = breakdown(&mut conn, &filter, &OPERATING_SYSTEMS)?;
+    let countries = breakdown(&mut conn, &filter, &COUNTRIES)?;
+    let cities = breakdown(&mut conn, &filter, &CITIES)?;
+    let traffic = top_values(
+        &mut conn,
+        &ClickFilter { class: ClassFilter::All, ..filter },
+        &TRAFFIC,
+        TRAFFIC_ROLLUP_ENTRIES,
+    )?;
+
+    Ok(Json(LinkStatsResponse {
+        short_code,
+        from,
+        to,
+        interval: params.interval,
+        class: params.class,
+        total_clicks: totals.clicks,
+        unique_visitors: totals.unique_visitors,
+        series,
+        referrers,
+        browsers,
+        operating_systems,
+        countries,
+        cities,
+        traffic,
+    }))
+}
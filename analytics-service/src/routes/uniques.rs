@@ -0,0 +1,98 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::{Days, NaiveDate, Utc};
+use common::uniques::unique_visitors_key;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::AppState;
+use crate::error::AnalyticsError;
+use crate::links::find_owned_code;
+
+/// Range used when the caller does not pass `from`
+const DEFAULT_RANGE_DAYS: u64 = 7;
+/// Longest range a single request may ask for
+const MAX_RANGE_DAYS: u64 = 366;
+
+#[derive(Deserialize)]
+pub struct UniquesParams {
+    user_id: i32,
+    /// First day (UTC), inclusive; defaults to 7 days ending at `to`
+    from: Option<NaiveDate>,
+    /// Last day (UTC), inclusive; defaults to today
+    to: Option<NaiveDate>,
+}
+
+#[derive(Serialize)]
+pub struct DailyUniques {
+    pub day: NaiveDate,
+    pub unique_visitors: u64,
+}
+
+#[derive(Serialize)]
+pub struct UniqueVisitorsResponse {
+    pub short_code: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Distinct visitors over the whole range; not the sum of `days`, since a
+    /// visitor coming back on several days is counted once
+    pub unique_visitors: u64,
+    pub days: Vec<DailyUniques>,
+}
+
+/// HyperLogLog estimate of distinct human visitors (about 0.8% standard error), per day and
+/// merged over the range. Visitors are only ever stored as salted fingerprints.
+pub async fn unique_visitors(
+    Path(code): Path<String>,
+    Query(params): Query<UniquesParams>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<UniqueVisitorsResponse>, AnalyticsError> {
+    let to = params.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = params
+        .from
+        .unwrap_or_else(|| to - Days::new(DEFAULT_RANGE_DAYS - 1));
+    if from > to {
+        return Err(AnalyticsError::InvalidRequest("from must not be after to".to_string()));
+    }
+    if from + Days::new(MAX_RANGE_DAYS) <= to {
+        return Err(AnalyticsError::InvalidRequest(format!(
+            "Range may span at most {} days",
+            MAX_RANGE_DAYS
+        )));
+    }
+
+    let short_code = {
+        let mut conn = state.db_pool.get()?;
+        find_owned_code(&mut conn, &code, params.user_id)?
+    };
+
+    let days: Vec<NaiveDate> = from.iter_days().take_while(|day| *day <= to).collect();
+    let keys: Vec<String> = days.iter().map(|day| unique_visitors_key(&short_code, *day)).collect();
+
+    let mut conn = state.redis.get_multiplexed_async_connection().await?;
+
+    // One PFCOUNT per day, then a multi-key PFCOUNT that merges the whole range
+    let mut pipe = redis::pipe();
+    for key in &keys {
+        pipe.pfcount(key);
+    }
+    pipe.pfcount(&keys);
+    let mut counts: Vec<u64> = pipe.query_async(&mut conn).await?;
+
+    let merged = counts.pop().unwrap_or_default();
+    let days = days
+        .into_iter()
+        .zip(counts)
+        .map(|(day, unique_visitors)| DailyUniques { day, unique_visitors })
+        .collect();
+
+    Ok(Json(UniqueVisitorsResponse {
+        short_code,
+        from,
+        to,
+        unique_visitors: merged,
+        days,
+    }))
+}
@@ -0,0 +1,35 @@
+diesel::table! {
+    click_events (id, clicked_at) {
+        id -> Int8,
+        short_url -> Varchar,
+        clicked_at -> Timestamp,
+        referrer -> Nullable<Text>,
+        user_agent -> Nullable<Text>,
+        accept_language -> Nullable<Varchar>,
+        ip_hash -> Nullable<Varchar>,
+        referrer_domain -> Nullable<Varchar>,
+        browser -> Nullable<Varchar>,
+        os -> Nullable<Varchar>,
+        country -> Nullable<Varchar>,
+        city -> Nullable<Varchar>,
+        traffic_class -> Varchar,
+    }
+}
+
+// Owned by shortener-service; only read here to check link ownership and for exports
+diesel::table! {
+    url_mapping (short_url) {
+        short_url -> Varchar,
+        alias -> Nullable<Varchar>,
+        long_url -> Varchar,
+        creation_date -> Timestamp,
+        expiration_date -> Nullable<Timestamp>,
+        user_id -> Nullable<Int4>,
+        click_count -> Int4,
+        deleted_at -> Nullable<Timestamp>,
+        disabled -> Bool,
+        redirect_type -> Int4,
+    }
+}
+
+diesel::allow_tables_to_appear_in_same_query!(click_events, url_mapping);
@@ -0,0 +1,23 @@
+use std::sync::LazyLock;
+
+use woothee::parser::Parser;
+
+static PARSER: LazyLock<Parser> = LazyLock::new(Parser::new);
+
+/// Browser and operating system names extracted from a User-Agent header
+pub struct Device {
+    pub browser: Option<String>,
+    pub os: Option<String>,
+}
+
+pub fn parse(user_agent: &str) -> Device {
+    let Some(result) = PARSER.parse(user_agent) else {
+        return Device { browser: None, os: None };
+    };
+
+    let known = |value: &str| Some(value.to_string()).filter(|value| value != "UNKNOWN");
+    Device {
+        browser: known(result.name),
+        os: known(result.os),
+    }
+}
@@ -1,8 +1,10 @@
 use utoipa::OpenApi;
 
 use crate::models::{
-    ApiResponse, Claims, LoginRequest, LoginResponse, RedirectResponse, RefreshTokenRequest,
-    ShortenRequest, ShortenResponse,
+    ApiResponse, BatchItemError, BatchItemResult, BatchShortenRequest, BatchShortenResponse, Claims, DailyUniques,
+    LinkInfoResponse, LinkStatsResponse, LoginRequest, LoginResponse, RedirectResponse, RefreshTokenRequest,
+    SetDisabledRequest, ShortenRequest, ShortenResponse, StatsBreakdownEntry, StatsSeriesPoint,
+    UniqueVisitorsResponse, UpdateLinkRequest,
 };
 
 // Define our API documentation
@@ -11,6 +13,15 @@ use crate::models::{
     paths(
         crate::handlers::health::health,
         crate::handlers::shortener::shorten_url,
+        crate::handlers::shortener::shorten_batch,
+        crate::handlers::links::update_link,
+        crate::handlers::links::delete_link,
+        crate::handlers::links::set_link_disabled,
+        crate::handlers::links::restore_link,
+        crate::handlers::analytics::get_link_stats,
+        crate::handlers::analytics::get_link_uniques,
+        crate::handlers::analytics::export_links,
+        crate::handlers::analytics::export_clicks,
         crate::handlers::redirect::redirect_url,
         crate::handlers::user::get_all_users,
         crate::handlers::user::register_user,
@@ -28,12 +39,28 @@ use crate::models::{
         schemas(
             ShortenRequest,
             ShortenResponse,
+            BatchShortenRequest,
+            BatchShortenResponse,
+            BatchItemResult,
+            BatchItemError,
+            UpdateLinkRequest,
+            LinkInfoResponse,
+            SetDisabledRequest,
+            LinkStatsResponse,
+            StatsSeriesPoint,
+            StatsBreakdownEntry,
+            UniqueVisitorsResponse,
+            DailyUniques,
             RedirectResponse,
             LoginRequest,
             LoginResponse,
             RefreshTokenRequest,
             Claims,
             ApiResponse<ShortenResponse>,
+            ApiResponse<BatchShortenResponse>,
+            ApiResponse<LinkInfoResponse>,
+            ApiResponse<LinkStatsResponse>,
+            ApiResponse<UniqueVisitorsResponse>,
             ApiResponse<RedirectResponse>,
             ApiResponse<LoginResponse>,
             ApiResponse<()>
@@ -1,10 +1,28 @@
+use reqwest::header::{HeaderMap, HeaderValue};
 use reqwest::Client;
 use std::sync::Arc;
 use crate::cache::TokenCache;
 
+/// Header the internal services expect the shared `SERVICE_TOKEN` in (`common::auth` on their side)
+const SERVICE_TOKEN_HEADER: &str = "x-service-token";
+
+/// Client for services that trust the `user_id` the gateway passes and therefore only accept
+/// requests carrying the service token
+fn internal_client(service_token: &str) -> Client {
+    let mut token = HeaderValue::from_str(service_token).expect("SERVICE_TOKEN must be a valid header value");
+    token.set_sensitive(true);
+    let mut headers = HeaderMap::new();
+    headers.insert(SERVICE_TOKEN_HEADER, token);
+    Client::builder()
+        .default_headers(headers)
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
 pub struct AppState {
     pub shortener_client: Client,
     pub redirect_client: Client,
+    pub analytics_client: Client,
     pub user_service_client: Client,
     pub jwt_secret: String,
     pub token_cache: Arc<TokenCache>,
@@ -13,9 +31,11 @@ pub struct AppState {
 
 impl AppState {
     pub fn new() -> Self {
+        let service_token = std::env::var("SERVICE_TOKEN").expect("SERVICE_TOKEN must be set");
         AppState {
-            shortener_client: Client::new(),
+            shortener_client: internal_client(&service_token),
             redirect_client: Client::new(),
+            analytics_client: internal_client(&service_token),
             user_service_client: Client::new(),
             jwt_secret: std::env::var("JWT_SECRET").unwrap_or_else(|_| "super_secret_key".to_string()),
             token_cache: Arc::new(TokenCache::new()),
@@ -82,4 +82,14 @@ pub async fn validate_token(headers: &HeaderMap, state: &Arc<AppState>, token_ty
         }
         Err(_) => Err("Failed to validate token with user service".to_string()),
     }
+}
+
+/// Numeric user id carried by validated claims, as stored in `url_mapping.user_id`
+pub fn claims_user_id(claims: &Claims) -> Result<i32, String> {
+    claims
+        .user_id
+        .as_deref()
+        .unwrap_or(&claims.sub)
+        .parse::<i32>()
+        .map_err(|_| "Invalid user id in token".to_string())
 }
\ No newline at end of file
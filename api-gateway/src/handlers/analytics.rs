@@ -0,0 +1,258 @@
+// handlers/analytics.rs
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::Arc;
+
+use crate::app_state::AppState;
+use crate::handlers::links::require_user_id;
+use crate::helpers::{forward_error_response, is_link_code, link_not_found_response};
+use crate::models::{ApiResponse, ExportQuery, LinkStatsResponse, StatsQuery, UniqueVisitorsResponse, UniquesQuery};
+
+//     GET /links/{code}/stats (Link Analytics)
+//     GET /links/{code}/uniques (Unique Visitor Estimate)
+//     GET /exports/links (Link Export)
+//     GET /exports/clicks (Click Export)
+
+// Relay an analytics-service response, wrapping successful bodies in `ApiResponse`
+async fn forward_analytics_response<T: DeserializeOwned + Serialize>(
+    result: Result<reqwest::Response, reqwest::Error>,
+) -> Response {
+    match result {
+        Ok(response) => {
+            if response.status().is_success() {
+                match response.json::<T>().await {
+                    Ok(data) => (
+                        StatusCode::OK,
+                        Json(ApiResponse {
+                            success: true,
+                            data: Some(data),
+                            message: None,
+                        }),
+                    )
+                        .into_response(),
+                    Err(_) => (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ApiResponse::<()> {
+                            success: false,
+                            data: None,
+                            message: Some("Failed to parse analytics service response".to_string()),
+                        }),
+                    )
+                        .into_response(),
+                }
+            } else {
+                forward_error_response(response, "Analytics service error").await
+            }
+        }
+        Err(_) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::<()> {
+                success: false,
+                data: None,
+                message: Some("Failed to connect to analytics service".to_string()),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+// Link stats endpoint
+#[utoipa::path(
+    get,
+    path = "/links/{code}/stats",
+    tag = "api-gateway",
+    params(
+        ("code" = String, Path, description = "Short code or alias of the link"),
+        ("from" = Option<String>, Query, description = "RFC 3339 start of the range, inclusive; defaults to 7 days before `to`"),
+        ("to" = Option<String>, Query, description = "RFC 3339 end of the range, exclusive; defaults to now"),
+        ("interval" = Option<String>, Query, description = "Time series bucket size: `hour` or `day` (default)"),
+        ("class" = Option<String>, Query, description = "Traffic to count: `human` (default), `bot`, `preview` or `all`")
+    ),
+    responses(
+        (status = 200, description = "Click statistics for the link", body = LinkStatsResponse),
+        (status = 400, description = "Invalid time range or interval"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - link belongs to another user"),
+        (status = 404, description = "Link not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_link_stats(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(code): Path<String>,
+    Query(query): Query<StatsQuery>,
+) -> Response {
+    let user_id = match require_user_id(&headers, &state).await {
+        Ok(user_id) => user_id,
+        Err(response) => return response,
+    };
+    if !is_link_code(&code) {
+        return link_not_found_response();
+    }
+
+    let result = state
+        .analytics_client
+        .get(format!("http://analytics-service:8083/links/{}/stats", code))
+        .query(&[("user_id", user_id)])
+        .query(&query)
+        .send()
+        .await;
+
+    forward_analytics_response::<LinkStatsResponse>(result).await
+}
+
+// Unique visitors endpoint
+#[utoipa::path(
+    get,
+    path = "/links/{code}/uniques",
+    tag = "api-gateway",
+    params(
+        ("code" = String, Path, description = "Short code or alias of the link"),
+        ("from" = Option<String>, Query, description = "First day (`YYYY-MM-DD`, UTC), inclusive; defaults to 7 days ending at `to`"),
+        ("to" = Option<String>, Query, description = "Last day (`YYYY-MM-DD`, UTC), inclusive; defaults to today")
+    ),
+    responses(
+        (status = 200, description = "Estimated unique visitors per day and over the range", body = UniqueVisitorsResponse),
+        (status = 400, description = "Invalid date range"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - link belongs to another user"),
+        (status = 404, description = "Link not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn get_link_uniques(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(code): Path<String>,
+    Query(query): Query<UniquesQuery>,
+) -> Response {
+    let user_id = match require_user_id(&headers, &state).await {
+        Ok(user_id) => user_id,
+        Err(response) => return response,
+    };
+    if !is_link_code(&code) {
+        return link_not_found_response();
+    }
+
+    let result = state
+        .analytics_client
+        .get(format!("http://analytics-service:8083/links/{}/uniques", code))
+        .query(&[("user_id", user_id)])
+        .query(&query)
+        .send()
+        .await;
+
+    forward_analytics_response::<UniqueVisitorsResponse>(result).await
+}
+
+// Relay an export as it streams in, keeping the file type and name chosen by analytics-service
+async fn forward_export(state: &Arc<AppState>, headers: &HeaderMap, table: &str, query: &ExportQuery) -> Response {
+    let user_id = match require_user_id(headers, state).await {
+        Ok(user_id) => user_id,
+        Err(response) => return response,
+    };
+
+    let result = state
+        .analytics_client
+        .get(format!("http://analytics-service:8083/exports/{}", table))
+        .query(&[("user_id", user_id)])
+        .query(query)
+        .send()
+        .await;
+
+    match result {
+        Ok(response) if response.status().is_success() => {
+            let mut builder = Response::builder().status(StatusCode::OK);
+            for (name, upstream) in [
+                (header::CONTENT_TYPE, reqwest::header::CONTENT_TYPE),
+                (header::CONTENT_DISPOSITION, reqwest::header::CONTENT_DISPOSITION),
+            ] {
+                if let Some(value) = response.headers().get(upstream) {
+                    builder = builder.header(name, value.as_bytes());
+                }
+            }
+            builder
+                .body(Body::from_stream(response.bytes_stream()))
+                .unwrap_or_else(|_| StatusCode::BAD_GATEWAY.into_response())
+        }
+        Ok(response) => forward_error_response(response, "Analytics service error").await,
+        Err(_) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::<()> {
+                success: false,
+                data: None,
+                message: Some("Failed to connect to analytics service".to_string()),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+// Link export endpoint
+#[utoipa::path(
+    get,
+    path = "/exports/links",
+    tag = "api-gateway",
+    params(
+        ("format" = Option<String>, Query, description = "`csv` (default), `jsonl` or `parquet`"),
+        ("from" = Option<String>, Query, description = "RFC 3339 lower bound on the creation time, inclusive"),
+        ("to" = Option<String>, Query, description = "RFC 3339 upper bound on the creation time, exclusive")
+    ),
+    responses(
+        (status = 200, description = "All of the caller's links, including deleted and disabled ones, streamed as a file"),
+        (status = 400, description = "Invalid format or time range"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn export_links(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<ExportQuery>,
+) -> Response {
+    forward_export(&state, &headers, "links", &query).await
+}
+
+// Click export endpoint
+#[utoipa::path(
+    get,
+    path = "/exports/clicks",
+    tag = "api-gateway",
+    params(
+        ("format" = Option<String>, Query, description = "`csv` (default), `jsonl` or `parquet`"),
+        ("from" = Option<String>, Query, description = "RFC 3339 lower bound on the click time, inclusive"),
+        ("to" = Option<String>, Query, description = "RFC 3339 upper bound on the click time, exclusive")
+    ),
+    responses(
+        (status = 200, description = "Raw click events on the caller's links, streamed as a file. Clicks past the retention period are only kept as daily aggregates and are not included."),
+        (status = 400, description = "Invalid format or time range"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn export_clicks(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<ExportQuery>,
+) -> Response {
+    forward_export(&state, &headers, "clicks", &query).await
+}
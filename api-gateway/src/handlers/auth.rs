@@ -46,15 +46,14 @@ pub async fn login(
             match response.json::<serde_json::Value>().await {
                 Ok(data) => {
                     // If login was successful, store tokens in cache
-                    if status.is_success() {
-                        if let (Some(access_token), Some(user_id)) = (
-                            data["data"]["access_token"].as_str(),
-                            data["data"]["user_id"].as_str(),
-                        ) {
-                            // Store access token in cache with default expiration (15min)
-                            let exp_time = chrono::Utc::now() + chrono::Duration::minutes(15);
-                            state.token_cache.store_token(access_token, user_id.to_string(), exp_time);
-                        }
+                    if let (true, Some(access_token), Some(user_id)) = (
+                        status.is_success(),
+                        data["data"]["access_token"].as_str(),
+                        data["data"]["user_id"].as_str(),
+                    ) {
+                        // Store access token in cache with default expiration (15min)
+                        let exp_time = chrono::Utc::now() + chrono::Duration::minutes(15);
+                        state.token_cache.store_token(access_token, user_id.to_string(), exp_time);
                     }
                     
                     (
@@ -209,15 +208,14 @@ pub async fn refresh_token(
             match response.json::<serde_json::Value>().await {
                 Ok(data) => {
                     // If refresh was successful, update cache with new token
-                    if status.is_success() {
-                        if let (Some(access_token), Some(user_id)) = (
-                            data["data"]["access_token"].as_str(),
-                            data["data"]["user_id"].as_str(),
-                        ) {
-                            // Store new access token in cache
-                            let exp_time = chrono::Utc::now() + chrono::Duration::minutes(15);
-                            state.token_cache.store_token(access_token, user_id.to_string(), exp_time);
-                        }
+                    if let (true, Some(access_token), Some(user_id)) = (
+                        status.is_success(),
+                        data["data"]["access_token"].as_str(),
+                        data["data"]["user_id"].as_str(),
+                    ) {
+                        // Store new access token in cache
+                        let exp_time = chrono::Utc::now() + chrono::Duration::minutes(15);
+                        state.token_cache.store_token(access_token, user_id.to_string(), exp_time);
                     }
                     
                     (
@@ -289,27 +287,25 @@ pub async fn validate_token(
             match response.json::<serde_json::Value>().await {
                 Ok(data) => {
                     // If token is valid, add to cache
-                    if let (Some(valid), Some(user_id)) = (
+                    if let (Some(true), Some(user_id)) = (
                         data["data"]["valid"].as_bool(),
                         data["data"]["user_id"].as_str(),
                     ) {
-                        if valid {
-                            // Add token to cache with default expiration
-                            let exp_time = chrono::Utc::now() + chrono::Duration::minutes(15);
-                            state.token_cache.store_token(&request.token, user_id.to_string(), exp_time);
-                            
-                            return (
-                                StatusCode::OK,
-                                Json(ApiResponse {
-                                    success: true,
-                                    data: Some(ValidateTokenResponse {
-                                        valid: true,
-                                        user_id: Some(user_id.to_string()),
-                                    }),
-                                    message: None,
+                        // Add token to cache with default expiration
+                        let exp_time = chrono::Utc::now() + chrono::Duration::minutes(15);
+                        state.token_cache.store_token(&request.token, user_id.to_string(), exp_time);
+                        
+                        return (
+                            StatusCode::OK,
+                            Json(ApiResponse {
+                                success: true,
+                                data: Some(ValidateTokenResponse {
+                                    valid: true,
+                                    user_id: Some(user_id.to_string()),
                                 }),
-                            );
-                        }
+                                message: None,
+                            }),
+                        );
                     }
                     
                     // Parse the response appropriately
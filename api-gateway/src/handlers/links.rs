@@ -0,0 +1,257 @@
+// handlers/links.rs
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::sync::Arc;
+
+use crate::app_state::AppState;
+use crate::auth::{claims_user_id, validate_token};
+use crate::helpers::{forward_error_response, is_link_code, link_not_found_response};
+use crate::models::{ApiResponse, LinkInfoResponse, SetDisabledRequest};
+
+//     PATCH /links/{code} (Edit Link)
+//     DELETE /links/{code} (Soft Delete Link)
+//     PUT /links/{code}/disabled (Pause or Resume Link)
+//     POST /links/{code}/restore (Restore Deleted Link)
+
+// Resolve the caller's user id; link management and stats always require a valid access token
+pub(crate) async fn require_user_id(headers: &HeaderMap, state: &Arc<AppState>) -> Result<i32, Response> {
+    validate_token(headers, state, "access")
+        .await
+        .and_then(|claims| claims_user_id(&claims))
+        .map_err(|err| {
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(ApiResponse::<()> {
+                    success: false,
+                    data: None,
+                    message: Some(err),
+                }),
+            )
+                .into_response()
+        })
+}
+
+// Relay a shortener-service response that carries link details
+async fn forward_link_response(result: Result<reqwest::Response, reqwest::Error>) -> Response {
+    match result {
+        Ok(response) => {
+            if response.status().is_success() {
+                match response.json::<LinkInfoResponse>().await {
+                    Ok(data) => (
+                        StatusCode::OK,
+                        Json(ApiResponse {
+                            success: true,
+                            data: Some(data),
+                            message: None,
+                        }),
+                    )
+                        .into_response(),
+                    Err(_) => (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ApiResponse::<LinkInfoResponse> {
+                            success: false,
+                            data: None,
+                            message: Some("Failed to parse shortener service response".to_string()),
+                        }),
+                    )
+                        .into_response(),
+                }
+            } else {
+                forward_error_response(response, "Shortener service error").await
+            }
+        }
+        Err(_) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::<LinkInfoResponse> {
+                success: false,
+                data: None,
+                message: Some("Failed to connect to shortener service".to_string()),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+// Edit link endpoint
+#[utoipa::path(
+    patch,
+    path = "/links/{code}",
+    tag = "api-gateway",
+    params(
+        ("code" = String, Path, description = "Short code of the link")
+    ),
+    request_body = UpdateLinkRequest,
+    responses(
+        (status = 200, description = "Link updated", body = LinkInfoResponse),
+        (status = 400, description = "Malformed body, or invalid URL, alias or expiration time"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - link belongs to another user"),
+        (status = 404, description = "Link not found"),
+        (status = 409, description = "Alias already taken"),
+        (status = 422, description = "Destination URL is not allowed"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn update_link(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(code): Path<String>,
+    // Forwarded untouched so an explicit `"alias": null` survives the hop
+    Json(request): Json<serde_json::Value>,
+) -> Response {
+    let user_id = match require_user_id(&headers, &state).await {
+        Ok(user_id) => user_id,
+        Err(response) => return response,
+    };
+    if !is_link_code(&code) {
+        return link_not_found_response();
+    }
+
+    let result = state
+        .shortener_client
+        .patch(format!("http://shortener-service:8080/links/{}", code))
+        .query(&[("user_id", user_id)])
+        .json(&request)
+        .send()
+        .await;
+
+    forward_link_response(result).await
+}
+
+// Delete link endpoint
+#[utoipa::path(
+    delete,
+    path = "/links/{code}",
+    tag = "api-gateway",
+    params(
+        ("code" = String, Path, description = "Short code of the link")
+    ),
+    responses(
+        (status = 200, description = "Link deleted; it can be restored within the retention window", body = LinkInfoResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - link belongs to another user"),
+        (status = 404, description = "Link not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn delete_link(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(code): Path<String>,
+) -> Response {
+    let user_id = match require_user_id(&headers, &state).await {
+        Ok(user_id) => user_id,
+        Err(response) => return response,
+    };
+    if !is_link_code(&code) {
+        return link_not_found_response();
+    }
+
+    let result = state
+        .shortener_client
+        .delete(format!("http://shortener-service:8080/links/{}", code))
+        .query(&[("user_id", user_id)])
+        .send()
+        .await;
+
+    forward_link_response(result).await
+}
+
+// Disable link endpoint
+#[utoipa::path(
+    put,
+    path = "/links/{code}/disabled",
+    tag = "api-gateway",
+    params(
+        ("code" = String, Path, description = "Short code of the link")
+    ),
+    request_body = SetDisabledRequest,
+    responses(
+        (status = 200, description = "Link paused or resumed", body = LinkInfoResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - link belongs to another user"),
+        (status = 404, description = "Link not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn set_link_disabled(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(code): Path<String>,
+    Json(request): Json<SetDisabledRequest>,
+) -> Response {
+    let user_id = match require_user_id(&headers, &state).await {
+        Ok(user_id) => user_id,
+        Err(response) => return response,
+    };
+    if !is_link_code(&code) {
+        return link_not_found_response();
+    }
+
+    let result = state
+        .shortener_client
+        .put(format!("http://shortener-service:8080/links/{}/disabled", code))
+        .query(&[("user_id", user_id)])
+        .json(&request)
+        .send()
+        .await;
+
+    forward_link_response(result).await
+}
+
+// Restore link endpoint
+#[utoipa::path(
+    post,
+    path = "/links/{code}/restore",
+    tag = "api-gateway",
+    params(
+        ("code" = String, Path, description = "Short code of the link")
+    ),
+    responses(
+        (status = 200, description = "Link restored", body = LinkInfoResponse),
+        (status = 400, description = "Link is not deleted"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - link belongs to another user"),
+        (status = 404, description = "Link not found"),
+        (status = 410, description = "Retention window has passed"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn restore_link(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(code): Path<String>,
+) -> Response {
+    let user_id = match require_user_id(&headers, &state).await {
+        Ok(user_id) => user_id,
+        Err(response) => return response,
+    };
+    if !is_link_code(&code) {
+        return link_not_found_response();
+    }
+
+    let result = state
+        .shortener_client
+        .post(format!("http://shortener-service:8080/links/{}/restore", code))
+        .query(&[("user_id", user_id)])
+        .send()
+        .await;
+
+    forward_link_response(result).await
+}
@@ -4,10 +4,14 @@ pub mod shortener;
 pub mod redirect;
 pub mod user;
 pub mod auth;
+pub mod links;
+pub mod analytics;
 
 // Re-export all handlers
 pub use health::health;
-pub use shortener::shorten_url;
+pub use shortener::{shorten_url, shorten_batch};
 pub use redirect::redirect_url;
 pub use user::{get_all_users,register_user, get_user, update_user, delete_user, change_password};
-pub use auth::{login, logout, refresh_token, validate_token};
\ No newline at end of file
+pub use auth::{login, logout, refresh_token, validate_token};
+pub use links::{update_link, delete_link, set_link_disabled, restore_link};
+pub use analytics::{export_clicks, export_links, get_link_stats, get_link_uniques};
\ No newline at end of file
@@ -21,6 +21,7 @@ use crate::models::{ApiResponse, RedirectResponse};
     responses(
         (status = 200, description = "Redirect information", body = RedirectResponse),
         (status = 404, description = "Shortcode not found"),
+        (status = 410, description = "Link was disabled or deleted"),
         (status = 500, description = "Internal server error")
     )
 )]
@@ -32,6 +33,8 @@ pub async fn redirect_url(
     match state
         .redirect_client
         .get(format!("http://redirect-service:8081/{}", shortcode))
+        // Ask for the link description rather than the redirect itself
+        .header(reqwest::header::ACCEPT, "application/json")
         .send()
         .await
     {
@@ -64,6 +67,15 @@ pub async fn redirect_url(
                         message: Some("Shortcode not found".to_string()),
                     }),
                 )
+            } else if response.status().as_u16() == StatusCode::GONE.as_u16() {
+                (
+                    StatusCode::GONE,
+                    Json(ApiResponse::<RedirectResponse> {
+                        success: false,
+                        data: None,
+                        message: Some("Link is no longer available".to_string()),
+                    }),
+                )
             } else {
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
@@ -1,14 +1,39 @@
 // handlers/shortener.rs
 use axum::{
     extract::State,
-    http::StatusCode,
-    response::IntoResponse,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use std::sync::Arc;
 
 use crate::app_state::AppState;
-use crate::models::{ApiResponse, ShortenRequest, ShortenResponse};
+use crate::auth::{claims_user_id, validate_token};
+use crate::models::{ApiResponse, BatchShortenRequest, BatchShortenResponse, ShortenRequest, ShortenResponse};
+use crate::helpers::forward_error_response;
+
+// Anonymous shortening is allowed; signed-in users get their links tied to their account
+async fn optional_user_id(headers: &HeaderMap, state: &Arc<AppState>) -> Result<Option<i32>, Response> {
+    if !headers.contains_key("Authorization") {
+        return Ok(None);
+    }
+
+    validate_token(headers, state, "access")
+        .await
+        .and_then(|claims| claims_user_id(&claims))
+        .map(Some)
+        .map_err(|err| {
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(ApiResponse::<()> {
+                    success: false,
+                    data: None,
+                    message: Some(err),
+                }),
+            )
+                .into_response()
+        })
+}
 
 // Shortener service endpoint
 #[utoipa::path(
@@ -18,8 +43,12 @@ use crate::models::{ApiResponse, ShortenRequest, ShortenResponse};
     request_body = ShortenRequest,
     responses(
         (status = 200, description = "URL shortened successfully", body = ShortenResponse),
+        (status = 400, description = "Malformed body, or invalid URL, alias or expiration time"),
         (status = 401, description = "Unauthorized"),
-        (status = 500, description = "Internal server error")
+        (status = 409, description = "Alias already taken"),
+        (status = 422, description = "Destination URL is not allowed"),
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Shortener service or its database is unavailable, or no short code is left")
     ),
     security(
         ("jwt" = [])
@@ -27,10 +56,13 @@ use crate::models::{ApiResponse, ShortenRequest, ShortenResponse};
 )]
 pub async fn shorten_url(
     State(state): State<Arc<AppState>>,
-    Json(request): Json<ShortenRequest>,
-) -> impl IntoResponse {
-    // Validate token using the user service
-
+    headers: HeaderMap,
+    Json(mut request): Json<ShortenRequest>,
+) -> Response {
+    match optional_user_id(&headers, &state).await {
+        Ok(user_id) => request.user_id = user_id,
+        Err(response) => return response,
+    }
 
     // Forward request to shortener service
     match state
@@ -50,35 +82,103 @@ pub async fn shorten_url(
                             data: Some(data),
                             message: None,
                         }),
-                    ),
+                    )
+                        .into_response(),
                     Err(_) => (
                         StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ApiResponse::<ShortenResponse> {
+                            success: false,
+                            data: None,
+                            message: Some("Failed to parse shortener service response".to_string()),
+                        }),
+                    )
+                        .into_response(),
+                }
+            } else {
+                forward_error_response(response, "Shortener service error").await
+            }
+        }
+        Err(_) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::<ShortenResponse> {
+                success: false,
+                data: None,
+                message: Some("Failed to connect to shortener service".to_string()),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+// Batch shortener endpoint
+#[utoipa::path(
+    post,
+    path = "/shorten/batch",
+    tag = "api-gateway",
+    request_body = BatchShortenRequest,
+    responses(
+        (status = 200, description = "Per-item results in request order", body = BatchShortenResponse),
+        (status = 400, description = "Empty or oversized batch"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Shortener service or its database is unavailable, or no short code is left")
+    ),
+    security(
+        ("jwt" = [])
+    )
+)]
+pub async fn shorten_batch(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(mut request): Json<BatchShortenRequest>,
+) -> Response {
+    match optional_user_id(&headers, &state).await {
+        Ok(user_id) => request.items.iter_mut().for_each(|item| item.user_id = user_id),
+        Err(response) => return response,
+    }
+
+    // Forward request to shortener service
+    match state
+        .shortener_client
+        .post("http://shortener-service:8080/shorten/batch")
+        .json(&request)
+        .send()
+        .await
+    {
+        Ok(response) => {
+            if response.status().is_success() {
+                match response.json::<BatchShortenResponse>().await {
+                    Ok(data) => (
+                        StatusCode::OK,
                         Json(ApiResponse {
+                            success: true,
+                            data: Some(data),
+                            message: None,
+                        }),
+                    )
+                        .into_response(),
+                    Err(_) => (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ApiResponse::<BatchShortenResponse> {
                             success: false,
                             data: None,
                             message: Some("Failed to parse shortener service response".to_string()),
                         }),
-                    ),
+                    )
+                        .into_response(),
                 }
             } else {
-                (
-                    StatusCode::from_u16(response.status().as_u16())
-                        .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-                    Json(ApiResponse {
-                        success: false,
-                        data: None,
-                        message: Some("Shortener service error".to_string()),
-                    }),
-                )
+                forward_error_response(response, "Shortener service error").await
             }
         }
         Err(_) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ApiResponse {
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ApiResponse::<BatchShortenResponse> {
                 success: false,
                 data: None,
                 message: Some("Failed to connect to shortener service".to_string()),
             }),
-        ),
+        )
+            .into_response(),
     }
-}
\ No newline at end of file
+}
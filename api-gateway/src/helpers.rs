@@ -11,4 +11,48 @@ pub fn convert_axum_to_reqwest_headers(axum_headers: &axum::http::HeaderMap) ->
     }
     
     reqwest_headers
-}
\ No newline at end of file
+}
+
+/// Relays an unsuccessful upstream response with its original status code.
+/// JSON error bodies (`{"error": ..., "message": ...}`) are passed through untouched so clients
+/// see the same machine-readable error the service produced.
+pub async fn forward_error_response(response: reqwest::Response, fallback_message: &str) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    let status = axum::http::StatusCode::from_u16(response.status().as_u16())
+        .unwrap_or(axum::http::StatusCode::BAD_GATEWAY);
+
+    match response.json::<serde_json::Value>().await {
+        Ok(body) => (status, axum::Json(body)).into_response(),
+        Err(_) => (
+            status,
+            axum::Json(crate::models::ApiResponse::<()> {
+                success: false,
+                data: None,
+                message: Some(fallback_message.to_string()),
+            }),
+        )
+            .into_response(),
+    }
+}
+
+/// Whether a path segment can name a link: short codes and custom aliases only use letters,
+/// digits, '-' and '_'. Checked before the code is put into an upstream URL, so a percent-decoded
+/// `/`, `?` or `..` cannot reach a different shortener or analytics route.
+pub fn is_link_code(code: &str) -> bool {
+    !code.is_empty() && code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The `link_not_found` body the services return for unknown codes, for codes rejected by `is_link_code`
+pub fn link_not_found_response() -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    (
+        axum::http::StatusCode::NOT_FOUND,
+        axum::Json(serde_json::json!({
+            "error": "link_not_found",
+            "message": "No link found for this short code",
+        })),
+    )
+        .into_response()
+}
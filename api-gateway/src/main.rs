@@ -8,7 +8,7 @@ mod helpers;
 
 use api_doc::ApiDoc;
 use app_state::AppState;
-use axum::{routing::{get, post, put, delete}, Router};
+use axum::{routing::{get, patch, post, put, delete}, Router};
 use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
 use utoipa::OpenApi;
@@ -35,7 +35,17 @@ async fn main() {
         // Core funtional proxied 
         .route("/health", get(handlers::health))
         .route("/shorten", post(handlers::shorten_url))
+        .route("/shorten/batch", post(handlers::shorten_batch))
         .route("/r/:shortcode", get(handlers::redirect_url))
+        // Link management, owner only
+        .route("/links/:code", patch(handlers::update_link))
+        .route("/links/:code", delete(handlers::delete_link))
+        .route("/links/:code/disabled", put(handlers::set_link_disabled))
+        .route("/links/:code/restore", post(handlers::restore_link))
+        .route("/links/:code/stats", get(handlers::get_link_stats))
+        .route("/links/:code/uniques", get(handlers::get_link_uniques))
+        .route("/exports/links", get(handlers::export_links))
+        .route("/exports/clicks", get(handlers::export_clicks))
         // User service proxied endpoints
         .route("/users", get(handlers::get_all_users))
         .route("/users", post(handlers::register_user))
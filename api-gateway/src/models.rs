@@ -4,21 +4,182 @@ use utoipa::ToSchema;
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct ShortenRequest {
     pub long_url: String,
+    pub custom_alias: Option<String>,
+    /// RFC 3339 timestamp; defaults to 30 days from now
+    pub expiration_time: Option<String>,
+    #[serde(default)]
+    pub never_expires: bool,
+    /// One of `hash`, `random`, `sequential` or `words`; defaults to the service configuration
+    pub code_strategy: Option<String>,
+    /// Create a new link even if the caller already shortened the same destination
+    /// (implied by `expiration_time` and `never_expires`)
+    #[serde(default)]
+    pub allow_duplicate: bool,
+    /// HTTP status used for the redirect: 301, 302 (default), 307 or 308
+    pub redirect_type: Option<i32>,
+    /// Filled in from the access token, never taken from the client
+    #[serde(skip_deserializing)]
+    #[schema(read_only)]
+    pub user_id: Option<i32>,
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct ShortenResponse {
     pub short_code: String,
     pub short_url: String,
-    pub expiration_time: String,
+    pub expiration_time: Option<String>,
     pub created_at: String,
 }
 
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BatchShortenRequest {
+    pub items: Vec<ShortenRequest>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BatchItemError {
+    pub status: u16,
+    pub error: String,
+    pub message: String,
+}
+
+/// Outcome of one batch item, in the same position as in the request
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BatchItemResult {
+    pub index: usize,
+    pub result: Option<ShortenResponse>,
+    pub error: Option<BatchItemError>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BatchShortenResponse {
+    pub results: Vec<BatchItemResult>,
+}
+
+/// Only the fields present are changed
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct UpdateLinkRequest {
+    pub long_url: Option<String>,
+    /// Send `null` to remove the alias
+    pub alias: Option<String>,
+    /// RFC 3339 timestamp
+    pub expiration_time: Option<String>,
+    #[serde(default)]
+    pub never_expires: bool,
+    /// 301, 302, 307 or 308
+    pub redirect_type: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct LinkInfoResponse {
+    pub short_code: String,
+    pub format_short_url: String,
+    pub long_url: String,
+    pub alias: Option<String>,
+    pub creation_date: String,
+    pub expiration_date: Option<String>,
+    pub click_count: i32,
+    pub disabled: bool,
+    pub redirect_type: i32,
+    pub deleted_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SetDisabledRequest {
+    pub disabled: bool,
+}
+
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct RedirectResponse {
     pub short_url: String,
     pub alias: Option<String>,
     pub long_url: String,
+    pub redirect_type: i32,
+    pub expiration_date: Option<String>,
+}
+
+// Analytics models
+#[derive(Serialize, Deserialize)]
+pub struct StatsQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct StatsSeriesPoint {
+    pub bucket: String,
+    pub clicks: i64,
+    pub unique_visitors: i64,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct StatsBreakdownEntry {
+    pub key: String,
+    pub clicks: i64,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct LinkStatsResponse {
+    pub short_code: String,
+    pub from: String,
+    pub to: String,
+    /// `hour` or `day`
+    pub interval: String,
+    /// `human`, `bot`, `preview` or `all`
+    pub class: String,
+    pub total_clicks: i64,
+    pub unique_visitors: i64,
+    pub series: Vec<StatsSeriesPoint>,
+    /// Referrer domains; `direct` when no referrer was sent
+    pub referrers: Vec<StatsBreakdownEntry>,
+    pub browsers: Vec<StatsBreakdownEntry>,
+    pub operating_systems: Vec<StatsBreakdownEntry>,
+    /// ISO 3166-1 alpha-2 country codes
+    pub countries: Vec<StatsBreakdownEntry>,
+    pub cities: Vec<StatsBreakdownEntry>,
+    /// Clicks per traffic class, regardless of `class`
+    pub traffic: Vec<StatsBreakdownEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ExportQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct UniquesQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct DailyUniques {
+    /// `YYYY-MM-DD` (UTC)
+    pub day: String,
+    pub unique_visitors: u64,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct UniqueVisitorsResponse {
+    pub short_code: String,
+    pub from: String,
+    pub to: String,
+    /// Distinct visitors over the whole range, not the sum of the daily values
+    pub unique_visitors: u64,
+    pub days: Vec<DailyUniques>,
 }
 
 // User related models
@@ -29,6 +190,7 @@ pub struct UserRegistrationRequest {
     pub password: String,
 }
 
+#[expect(dead_code, reason = "only named as a response body in the OpenAPI docs; user-service JSON is relayed as is")]
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct UserResponse {
     pub id: String,
@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+
+/// Header carrying the secret the API gateway shares with the internal services
+pub const SERVICE_TOKEN_HEADER: &str = "x-service-token";
+
+/// Shorter secrets are rejected at startup
+const MIN_SERVICE_TOKEN_LENGTH: usize = 32;
+
+/// Proves a request came through the API gateway, which already checked the caller's JWT.
+/// Internal services take the owner from request parameters, so they must not accept
+/// requests from anyone else.
+#[derive(Clone)]
+pub struct ServiceToken(Arc<str>);
+
+impl ServiceToken {
+    /// Reads `SERVICE_TOKEN`; panics when it is missing or too short so a service never runs unprotected
+    pub fn from_env() -> Self {
+        let token = std::env::var("SERVICE_TOKEN").expect("SERVICE_TOKEN must be set");
+        if token.len() < MIN_SERVICE_TOKEN_LENGTH {
+            panic!("SERVICE_TOKEN must be at least {} characters", MIN_SERVICE_TOKEN_LENGTH);
+        }
+        ServiceToken(token.into())
+    }
+
+    /// Looks at every byte, so the time taken does not reveal how much of the token matched
+    fn matches(&self, presented: &[u8]) -> bool {
+        let expected = self.0.as_bytes();
+        expected.len() == presented.len()
+            && expected.iter().zip(presented).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+    }
+}
+
+/// Middleware answering 401 to requests without the gateway's service token
+pub async fn require_service_token(State(token): State<ServiceToken>, request: Request, next: Next) -> Response {
+    let presented = request.headers().get(SERVICE_TOKEN_HEADER);
+    if presented.is_some_and(|value| token.matches(value.as_bytes())) {
+        return next.run(request).await;
+    }
+
+    (
+        StatusCode::UNAUTHORIZED,
+        Json(serde_json::json!({
+            "error": "unauthorized",
+            "message": "Requests must come through the API gateway",
+        })),
+    )
+        .into_response()
+}
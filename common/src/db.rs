@@ -1,6 +1,8 @@
 use diesel::pg::PgConnection;
 use diesel::r2d2::{self, ConnectionManager, Pool};
+use diesel::connection::SimpleConnection;
 use diesel::{RunQueryDsl,sql_query};
+use chrono::{Datelike, NaiveDate};
 
 pub type DbPool = Pool<ConnectionManager<PgConnection>>;
 
@@ -12,6 +14,8 @@ pub fn init_pool(url:&str) -> DbPool {
 }
 
 pub fn run_migrations(conn: &mut PgConnection) {
+    // Steps run in this order on every start; each one is idempotent and may only depend on earlier ones.
+    // New schema changes go at the end.
     let create_url_mapping_table_sql = r#"
         CREATE TABLE IF NOT EXISTS url_mapping (
             short_url VARCHAR(10) PRIMARY KEY,
@@ -24,9 +28,180 @@ pub fn run_migrations(conn: &mut PgConnection) {
         );
     "#;
 
+    // Short codes are derived from this sequence; MAXVALUE is 62^7, the 7-character base62 code space
+    let create_code_sequence_sql = r#"
+        CREATE SEQUENCE IF NOT EXISTS url_code_seq
+            START WITH 1
+            MINVALUE 1
+            MAXVALUE 3521614606207;
+    "#;
+
+    // Canonical destination used to deduplicate links per user
+    let add_canonical_url_sql = r#"
+        ALTER TABLE url_mapping ADD COLUMN IF NOT EXISTS canonical_url VARCHAR(1000) NULL;
+        CREATE INDEX IF NOT EXISTS url_mapping_user_canonical_idx ON url_mapping (user_id, canonical_url);
+    "#;
+
+    // Soft delete and pause support
+    let add_link_state_sql = r#"
+        ALTER TABLE url_mapping ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP NULL;
+        ALTER TABLE url_mapping ADD COLUMN IF NOT EXISTS disabled BOOLEAN NOT NULL DEFAULT FALSE;
+    "#;
+
+    // HTTP status used when redirecting; 302 matches what links did before it was configurable
+    let add_redirect_type_sql = r#"
+        ALTER TABLE url_mapping ADD COLUMN IF NOT EXISTS redirect_type INT NOT NULL DEFAULT 302
+            CHECK (redirect_type IN (301, 302, 307, 308));
+    "#;
+
+    // Link events written in the same transaction as the change they describe, and relayed to RabbitMQ afterwards
+    let create_outbox_sql = r#"
+        CREATE TABLE IF NOT EXISTS outbox (
+            id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+            routing_key VARCHAR(64) NOT NULL,
+            payload JSONB NOT NULL,
+            created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            sent_at TIMESTAMP NULL
+        );
+        CREATE INDEX IF NOT EXISTS outbox_pending_idx ON outbox (id) WHERE sent_at IS NULL;
+    "#;
 
+    // Lease on outbox rows a relay is publishing, so the rows are not held locked while it waits for the broker
+    let add_outbox_claim_sql = r#"
+        ALTER TABLE outbox ADD COLUMN IF NOT EXISTS claimed_until TIMESTAMP NULL;
+    "#;
 
-    sql_query(create_url_mapping_table_sql)
-        .execute(conn)
-        .expect("Failed to create url_mapping table");
-}
\ No newline at end of file
+    let steps: [(&str, &str); 7] = [
+        (create_url_mapping_table_sql, "create url_mapping table"),
+        (create_code_sequence_sql, "create url_code_seq sequence"),
+        (add_canonical_url_sql, "add url_mapping.canonical_url"),
+        (add_link_state_sql, "add url_mapping.deleted_at and url_mapping.disabled"),
+        (add_redirect_type_sql, "add url_mapping.redirect_type"),
+        (create_outbox_sql, "create outbox table"),
+        (add_outbox_claim_sql, "add outbox.claimed_until"),
+    ];
+    for (sql, step) in steps {
+        conn.batch_execute(sql)
+            .unwrap_or_else(|err| panic!("Failed to {}: {}", step, err));
+    }
+}
+
+/// Tables owned by analytics-service
+pub fn run_analytics_migrations(conn: &mut PgConnection) {
+    // Raw clicks, range-partitioned by month. Rows outside every monthly partition land in the default one.
+    let create_click_events_sql = r#"
+        CREATE TABLE IF NOT EXISTS click_events (
+            id BIGINT GENERATED ALWAYS AS IDENTITY,
+            short_url VARCHAR(10) NOT NULL,
+            clicked_at TIMESTAMP NOT NULL,
+            referrer TEXT NULL,
+            user_agent TEXT NULL,
+            accept_language VARCHAR(255) NULL,
+            ip_hash VARCHAR(64) NULL,
+            PRIMARY KEY (id, clicked_at)
+        ) PARTITION BY RANGE (clicked_at);
+        CREATE TABLE IF NOT EXISTS click_events_default PARTITION OF click_events DEFAULT;
+        CREATE INDEX IF NOT EXISTS click_events_short_url_idx ON click_events (short_url, clicked_at);
+    "#;
+
+    // Derived at ingest so stats queries only group by plain columns
+    let add_click_dimensions_sql = r#"
+        ALTER TABLE click_events ADD COLUMN IF NOT EXISTS referrer_domain VARCHAR(255) NULL;
+        ALTER TABLE click_events ADD COLUMN IF NOT EXISTS browser VARCHAR(64) NULL;
+        ALTER TABLE click_events ADD COLUMN IF NOT EXISTS os VARCHAR(64) NULL;
+        ALTER TABLE click_events ADD COLUMN IF NOT EXISTS country VARCHAR(2) NULL;
+        ALTER TABLE click_events ADD COLUMN IF NOT EXISTS city VARCHAR(128) NULL;
+        ALTER TABLE click_events ADD COLUMN IF NOT EXISTS traffic_class VARCHAR(16) NOT NULL DEFAULT 'human';
+    "#;
+
+    // Per-day aggregates that outlive the raw rows. Breakdowns are JSON objects of value -> clicks
+    // holding the most frequent values of that day. The state row records which days are rolled up
+    // and from which day on raw rows are still kept.
+    let create_click_daily_sql = r#"
+        CREATE TABLE IF NOT EXISTS click_daily (
+            short_code VARCHAR(10) NOT NULL,
+            day DATE NOT NULL,
+            traffic_class VARCHAR(16) NOT NULL,
+            clicks BIGINT NOT NULL,
+            uniques BIGINT NOT NULL,
+            top_referrers JSONB NOT NULL DEFAULT '{}',
+            browsers JSONB NOT NULL DEFAULT '{}',
+            operating_systems JSONB NOT NULL DEFAULT '{}',
+            countries JSONB NOT NULL DEFAULT '{}',
+            cities JSONB NOT NULL DEFAULT '{}',
+            PRIMARY KEY (short_code, day, traffic_class)
+        );
+        CREATE TABLE IF NOT EXISTS click_rollup_state (
+            id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+            rolled_up_until DATE NULL,
+            raw_since DATE NULL
+        );
+        INSERT INTO click_rollup_state (id) VALUES (TRUE) ON CONFLICT DO NOTHING;
+    "#;
+
+    conn.batch_execute(create_click_events_sql)
+        .expect("Failed to create click_events table");
+
+    conn.batch_execute(add_click_dimensions_sql)
+        .expect("Failed to add click_events breakdown columns");
+
+    conn.batch_execute(create_click_daily_sql)
+        .expect("Failed to create click_daily table");
+}
+
+/// Create the `click_events` partition for the month containing `day`, if it does not exist yet.
+/// Fails when the default partition already holds rows for that month.
+pub fn ensure_click_events_partition(conn: &mut PgConnection, day: NaiveDate) -> diesel::QueryResult<()> {
+    let start = day.with_day(1).expect("every month has a first day");
+    let end = start
+        .checked_add_months(chrono::Months::new(1))
+        .expect("partition range stays within chrono's calendar");
+
+    let create_partition_sql = format!(
+        "CREATE TABLE IF NOT EXISTS click_events_y{:04}m{:02} PARTITION OF click_events \
+         FOR VALUES FROM ('{}') TO ('{}')",
+        start.year(),
+        start.month(),
+        start,
+        end
+    );
+
+    sql_query(create_partition_sql).execute(conn)?;
+    Ok(())
+}
+
+#[derive(diesel::QueryableByName)]
+struct PartitionName {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    name: String,
+}
+
+/// Month covered by a partition named by `ensure_click_events_partition`; `None` for the default one
+fn partition_month(name: &str) -> Option<NaiveDate> {
+    let (year, month) = name.strip_prefix("click_events_y")?.split_once('m')?;
+    NaiveDate::from_ymd_opt(year.parse().ok()?, month.parse().ok()?, 1)
+}
+
+/// Drop every monthly `click_events` partition that ends on or before `cutoff`.
+/// Returns the names of the dropped partitions.
+pub fn drop_click_events_partitions_before(conn: &mut PgConnection, cutoff: NaiveDate) -> diesel::QueryResult<Vec<String>> {
+    let partitions: Vec<PartitionName> = sql_query(
+        "SELECT child.relname::text AS name \
+         FROM pg_inherits \
+         JOIN pg_class parent ON parent.oid = pg_inherits.inhparent \
+         JOIN pg_class child ON child.oid = pg_inherits.inhrelid \
+         WHERE parent.relname = 'click_events'",
+    )
+    .load(conn)?;
+
+    let mut dropped = Vec::new();
+    for PartitionName { name } in partitions {
+        let Some(start) = partition_month(&name) else { continue };
+        if start + chrono::Months::new(1) > cutoff {
+            continue;
+        }
+        sql_query(format!("DROP TABLE IF EXISTS {}", name)).execute(conn)?;
+        dropped.push(name);
+    }
+    Ok(dropped)
+}
@@ -0,0 +1,167 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Queue redirect-service publishes click events to and analytics-service consumes
+pub const CLICK_EVENTS_QUEUE: &str = "click_events";
+
+/// One followed redirect, as recorded for analytics.
+/// The client IP is never sent, only a salted hash of it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClickEvent {
+    pub short_url: String,
+    pub clicked_at: DateTime<Utc>,
+    pub referrer: Option<String>,
+    pub user_agent: Option<String>,
+    pub accept_language: Option<String>,
+    pub ip_hash: Option<String>,
+    /// ISO 3166-1 alpha-2 code, when the client could be located
+    #[serde(default)]
+    pub country: Option<String>,
+    #[serde(default)]
+    pub city: Option<String>,
+    #[serde(default)]
+    pub traffic_class: TrafficClass,
+}
+
+/// Who followed a link, as judged by redirect-service
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrafficClass {
+    #[default]
+    Human,
+    /// Crawlers, monitors, scripts and other automated clients
+    Bot,
+    /// Link unfurlers fetching the page to render a preview (Slack, Twitter, iMessage, ...)
+    Preview,
+}
+
+impl TrafficClass {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TrafficClass::Human => "human",
+            TrafficClass::Bot => "bot",
+            TrafficClass::Preview => "preview",
+        }
+    }
+}
+
+/// Topic exchange shortener-service publishes link lifecycle events to, routed by `LinkEventKind::routing_key`.
+/// Every redirect-service replica binds its own queue, so each one sees every event.
+pub const LINK_EVENTS_EXCHANGE: &str = "link_events";
+/// Binding pattern matching every link event
+pub const LINK_EVENTS_ALL: &str = "link.*";
+/// Schema version of `LinkEvent` written by this build; consumers reject any other
+pub const LINK_EVENT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkEventKind {
+    Created,
+    /// Destination, alias, expiration or redirect status changed, or the link was re-enabled or restored
+    Updated,
+    Deleted,
+    Disabled,
+}
+
+impl LinkEventKind {
+    /// `link.created`, `link.updated`, ...
+    pub fn routing_key(self) -> &'static str {
+        match self {
+            LinkEventKind::Created => "link.created",
+            LinkEventKind::Updated => "link.updated",
+            LinkEventKind::Deleted => "link.deleted",
+            LinkEventKind::Disabled => "link.disabled",
+        }
+    }
+}
+
+/// Everything a consumer needs to serve or drop a link without asking the database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkMetadata {
+    pub short_url: String,
+    pub alias: Option<String>,
+    pub long_url: String,
+    pub user_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    /// `None` never expires
+    pub expires_at: Option<DateTime<Utc>>,
+    pub redirect_type: u16,
+    pub disabled: bool,
+}
+
+/// Something happened to a link. Serialized as JSON; `version` comes first so any
+/// consumer can tell whether it understands the rest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkEvent {
+    pub version: u32,
+    pub kind: LinkEventKind,
+    pub occurred_at: DateTime<Utc>,
+    pub link: LinkMetadata,
+    /// Alias the link had before an update that changed or removed it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_alias: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum LinkEventError {
+    /// Written by a newer (or older) publisher with an incompatible schema
+    UnsupportedVersion(u32),
+    Malformed(serde_json::Error),
+}
+
+impl std::fmt::Display for LinkEventError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinkEventError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported link event version {} (expected {})",
+                version, LINK_EVENT_VERSION
+            ),
+            LinkEventError::Malformed(err) => write!(f, "malformed link event: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for LinkEventError {}
+
+impl LinkEvent {
+    pub fn new(kind: LinkEventKind, link: LinkMetadata) -> Self {
+        LinkEvent {
+            version: LINK_EVENT_VERSION,
+            kind,
+            occurred_at: Utc::now(),
+            link,
+            previous_alias: None,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("LinkEvent always serializes")
+    }
+
+    /// Parse an event, checking the version before the rest so that a schema change
+    /// is reported as such rather than as whatever field happens to fail first
+    pub fn decode(payload: &[u8]) -> Result<Self, LinkEventError> {
+        #[derive(Deserialize)]
+        struct Envelope {
+            version: u32,
+        }
+
+        let envelope: Envelope = serde_json::from_slice(payload).map_err(LinkEventError::Malformed)?;
+        if envelope.version != LINK_EVENT_VERSION {
+            return Err(LinkEventError::UnsupportedVersion(envelope.version));
+        }
+        serde_json::from_slice(payload).map_err(LinkEventError::Malformed)
+    }
+
+    /// Every key the link may be cached under: its code, its alias and its previous alias
+    pub fn cache_keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = std::iter::once(&self.link.short_url)
+            .chain(self.link.alias.as_ref())
+            .chain(self.previous_alias.as_ref())
+            .cloned()
+            .collect();
+        keys.dedup();
+        keys
+    }
+}
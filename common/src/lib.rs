@@ -1,3 +1,6 @@
 pub mod rabbitmq;
 pub mod logging;
-pub mod db;
\ No newline at end of file
+pub mod db;
+pub mod events;
+pub mod uniques;
+pub mod auth;
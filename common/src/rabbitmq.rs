@@ -1,37 +1,181 @@
-use lapin::{Connection, ConnectionProperties};
-use tokio::time::{sleep, timeout, Duration};
-use tracing::info;
+use std::future::Future;
+use std::sync::Arc;
 
-/// Connects to RabbitMQ with retry logic and timeout handling.
-pub async fn connect_to_rabbitmq(url: &str) -> Connection {
-    let max_retries = 10;
-    let base_delay = Duration::from_millis(500);
-    let max_delay = Duration::from_secs(5);
-    let total_timeout = Duration::from_secs(15);
+use lapin::{options::ExchangeDeclareOptions, types::FieldTable, Channel, Connection, ConnectionProperties, ConnectionState, ExchangeKind};
+use serde::Serialize;
+use tokio::sync::{watch, Notify};
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn};
 
-    let mut attempts = 0;
+use crate::events::LINK_EVENTS_EXCHANGE;
 
-    let connection = timeout(total_timeout, async {
+/// Backoff between connection attempts, doubling from `RECONNECT_BASE_DELAY` up to `RECONNECT_MAX_DELAY`
+const RECONNECT_BASE_DELAY: Duration = Duration::from_millis(500);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+/// How often an open connection is checked for having closed without reporting an error
+const STATUS_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Pause before a consumer session is set up again after it ended
+const CONSUMER_RESTART_DELAY: Duration = Duration::from_secs(1);
+
+/// Whether the manager currently holds an open connection
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AmqpState {
+    Connected,
+    /// Not connected yet, or the connection was lost; the manager keeps retrying
+    Connecting,
+}
+
+/// Body of the services' `/health` endpoints
+#[derive(Serialize)]
+pub struct Health {
+    /// `ok`, or `degraded` while RabbitMQ is unreachable (the service keeps serving requests)
+    pub status: &'static str,
+    pub rabbitmq: AmqpState,
+}
+
+/// Keeps one AMQP connection open for the lifetime of a service. Connecting happens in the
+/// background with backoff and never gives up, so services start while the broker is down, and
+/// a lost connection is replaced as soon as the broker is back.
+///
+/// Channels belong to a single connection: publishers open a new one through `create_channel` once
+/// theirs stops being connected, and consumers run through `run_consumer`, which sets them up again
+/// on every new connection.
+pub struct AmqpManager {
+    current: watch::Receiver<Option<Arc<Connection>>>,
+}
+
+impl AmqpManager {
+    /// Starts connecting to `url` in the background and returns right away
+    pub fn spawn(url: String) -> Arc<Self> {
+        let (sender, current) = watch::channel(None);
+        tokio::spawn(maintain_connection(url, sender));
+        Arc::new(AmqpManager { current })
+    }
+
+    pub fn state(&self) -> AmqpState {
+        match self.connection() {
+            Some(_) => AmqpState::Connected,
+            None => AmqpState::Connecting,
+        }
+    }
+
+    pub fn health(&self) -> Health {
+        let rabbitmq = self.state();
+        Health {
+            status: if rabbitmq == AmqpState::Connected { "ok" } else { "degraded" },
+            rabbitmq,
+        }
+    }
+
+    /// The open connection, or `None` while (re)connecting
+    pub fn connection(&self) -> Option<Arc<Connection>> {
+        self.current
+            .borrow()
+            .clone()
+            .filter(|conn| conn.status().connected())
+    }
+
+    /// Waits until a connection is open
+    pub async fn connected(&self) -> Arc<Connection> {
+        let mut current = self.current.clone();
         loop {
-            match Connection::connect(url, ConnectionProperties::default()).await {
-                Ok(conn) => {
-                    info!("✅ Connected to RabbitMQ!");
-                    return Ok(conn);
-                }
-                Err(e) => {
-                    attempts += 1;
-                    tracing::error!("🚨 Failed to connect to RabbitMQ (attempt {}/{}): {}", attempts, max_retries, e);
-                    if attempts >= max_retries {
-                        return Err("❌ Exceeded max retries. RabbitMQ is unavailable.");
-                    }
-                    
-                    let delay = std::cmp::min(base_delay * (2_u32.pow(attempts) as u32), max_delay);
-                    sleep(delay).await;
+            if let Some(conn) = self.connection() {
+                return conn;
+            }
+            // The sender lives in a task that never ends
+            current.changed().await.expect("AMQP connection task stopped");
+        }
+    }
+
+    /// Opens a channel on the current connection. Fails right away instead of waiting while
+    /// the broker is unreachable.
+    pub async fn create_channel(&self) -> Result<Channel, lapin::Error> {
+        match self.connection() {
+            Some(conn) => conn.create_channel().await,
+            None => Err(lapin::Error::InvalidConnectionState(ConnectionState::Closed)),
+        }
+    }
+
+    /// Runs `session` on a fresh channel of every connection, in the background. A session declares
+    /// the exchanges and queues it needs, starts consuming and returns once the consumer stream
+    /// ends, which happens when the connection is lost; it is then started again on the next one.
+    pub fn run_consumer<F, Fut>(self: &Arc<Self>, name: &'static str, mut session: F)
+    where
+        F: FnMut(Channel) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), lapin::Error>> + Send + 'static,
+    {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let conn = manager.connected().await;
+                let ended = match conn.create_channel().await {
+                    Ok(channel) => session(channel).await,
+                    Err(err) => Err(err),
+                };
+                match ended {
+                    Ok(()) => warn!("⚠️ {} consumer stopped, restarting it", name),
+                    Err(err) => warn!("⚠️ {} consumer failed, restarting it: {}", name, err),
                 }
+                sleep(CONSUMER_RESTART_DELAY).await;
+            }
+        });
+    }
+}
+
+async fn maintain_connection(url: String, current: watch::Sender<Option<Arc<Connection>>>) {
+    let mut attempts: u32 = 0;
+    loop {
+        let conn = match Connection::connect(&url, ConnectionProperties::default()).await {
+            Ok(conn) => conn,
+            Err(err) => {
+                let delay = RECONNECT_BASE_DELAY
+                    .saturating_mul(2_u32.saturating_pow(attempts))
+                    .min(RECONNECT_MAX_DELAY);
+                attempts = attempts.saturating_add(1);
+                warn!("🚨 Failed to connect to RabbitMQ (attempt {}), retrying in {:?}: {}", attempts, delay, err);
+                sleep(delay).await;
+                continue;
+            }
+        };
+        attempts = 0;
+
+        let lost = Arc::new(Notify::new());
+        let notify = lost.clone();
+        conn.on_error(move |err| {
+            warn!("🚨 RabbitMQ connection error: {}", err);
+            notify.notify_one();
+        });
+        let conn = Arc::new(conn);
+        current.send_replace(Some(conn.clone()));
+        info!("✅ Connected to RabbitMQ!");
+
+        // A broker-initiated close does not always surface as an error, so poll the status as well
+        while conn.status().connected() {
+            tokio::select! {
+                _ = lost.notified() => break,
+                _ = sleep(STATUS_CHECK_INTERVAL) => {}
             }
         }
-    })
-    .await;
+        current.send_replace(None);
+        warn!("⚠️ Lost the RabbitMQ connection, reconnecting");
+    }
+}
 
-    connection.expect("❌ Timed out waiting for RabbitMQ").expect("❌ Failed to connect to RabbitMQ")
+/// Declares the durable `link_events` topic exchange; publishers and consumers both call this,
+/// so whichever starts first creates it with the same settings.
+pub async fn declare_link_events_exchange(channel: &Channel) -> Result<(), lapin::Error> {
+    channel
+        .exchange_declare(
+            LINK_EVENTS_EXCHANGE,
+            ExchangeKind::Topic,
+            ExchangeDeclareOptions {
+                durable: true,
+                ..ExchangeDeclareOptions::default()
+            },
+            FieldTable::default(),
+        )
+        .await?;
+    info!("✅ Exchange declared: {}", LINK_EVENTS_EXCHANGE);
+    Ok(())
 }
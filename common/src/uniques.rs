@@ -0,0 +1,11 @@
+use chrono::NaiveDate;
+
+/// How long per-day HyperLogLog keys are kept in Redis
+pub const UNIQUE_VISITORS_TTL_SECS: i64 = 400 * 24 * 60 * 60;
+
+/// Redis HyperLogLog holding the visitor fingerprints of one link on one (UTC) day.
+/// The code is wrapped in a hash tag so all days of a link share a cluster slot,
+/// which multi-key `PFCOUNT` requires.
+pub fn unique_visitors_key(short_code: &str, day: NaiveDate) -> String {
+    format!("hll:{{{}}}:{}", short_code, day.format("%Y%m%d"))
+}
@@ -1,4 +1,40 @@
+use common::events::LinkMetadata;
 use redis::{AsyncCommands, Client, RedisResult};
+use serde::{Deserialize, Serialize};
+
+/// What redirect-service keeps in Redis for each short code
+#[derive(Serialize, Deserialize)]
+pub struct CachedLink {
+    /// Code the link is stored under, also when it was cached by alias
+    pub short_url: String,
+    pub long_url: String,
+    /// Unix timestamp (seconds) after which the link stops resolving; `None` never expires
+    pub expires_at: Option<i64>,
+    /// HTTP status to redirect with; entries written before it existed fall back to 302
+    #[serde(default = "default_redirect_type")]
+    pub redirect_type: u16,
+}
+
+fn default_redirect_type() -> u16 {
+    302
+}
+
+impl From<&LinkMetadata> for CachedLink {
+    fn from(link: &LinkMetadata) -> Self {
+        CachedLink {
+            short_url: link.short_url.clone(),
+            long_url: link.long_url.clone(),
+            expires_at: link.expires_at.map(|expires_at| expires_at.timestamp()),
+            redirect_type: link.redirect_type,
+        }
+    }
+}
+
+impl CachedLink {
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
 
 pub struct RedisCache {
     client: Client,
@@ -11,18 +47,34 @@ impl RedisCache {
         Ok(Self { client })
     }
 
-    /// Fetch a URL from Redis cache
-    pub async fn get(&self, short_code: &str) -> Option<String> {
+    /// Fetch a link from Redis cache; unreadable entries count as a miss
+    pub async fn get(&self, short_code: &str) -> Option<CachedLink> {
         let mut conn = self.client.get_multiplexed_async_connection().await.ok()?;
-        match conn.get(short_code).await {
-            Ok(url) => Some(url),
-            Err(_) => None,
-        }
+        let raw: String = conn.get(short_code).await.ok()?;
+        serde_json::from_str(&raw).ok()
+    }
+
+    /// Store a link in Redis cache for at most `max_ttl` seconds, and never past its expiration
+    pub async fn set(&self, short_code: &str, link: &CachedLink, max_ttl: u64) -> RedisResult<()> {
+        let ttl = match link.expires_at {
+            Some(expires_at) => {
+                let remaining = expires_at - chrono::Utc::now().timestamp();
+                if remaining <= 0 {
+                    return Ok(()); // Already expired, nothing worth caching
+                }
+                max_ttl.min(remaining as u64)
+            }
+            None => max_ttl,
+        };
+
+        let value = serde_json::to_string(link).expect("CachedLink always serializes");
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        conn.set_ex(short_code, value, ttl).await
     }
 
-    /// Store a URL in Redis cache with an expiry time
-    pub async fn set(&self, short_code: &str, original_url: &str, ttl: usize) -> RedisResult<()> {
+    /// Remove cached entries
+    pub async fn delete(&self, keys: &[String]) -> RedisResult<()> {
         let mut conn = self.client.get_multiplexed_async_connection().await?;
-        conn.set_ex(short_code, original_url, ttl.try_into().unwrap()).await
+        conn.del(keys).await
     }
 }
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::db::DbPool;
+use common::uniques::{unique_visitors_key, UNIQUE_VISITORS_TTL_SECS};
+use diesel::prelude::*;
+use redis::{AsyncCommands, Client, RedisResult};
+use tracing::{info, warn};
+
+use crate::schema::url_mapping::dsl::*;
+
+/// Hash of short code -> clicks not yet written to Postgres
+const PENDING_KEY: &str = "clicks:pending";
+/// Snapshot of `PENDING_KEY` being written to Postgres; only deleted once the write committed
+const FLUSHING_KEY: &str = "clicks:flushing";
+/// Keeps replicas from flushing the same snapshot twice
+const LOCK_KEY: &str = "clicks:flush_lock";
+const LOCK_TTL_SECS: u64 = 60;
+
+/// Deletes the lock only if this replica still holds it
+const RELEASE_LOCK_SCRIPT: &str = r#"
+    if redis.call("GET", KEYS[1]) == ARGV[1] then
+        return redis.call("DEL", KEYS[1])
+    end
+    return 0
+"#;
+
+type FlushError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Buffers redirect counts in Redis and periodically adds them to `url_mapping.click_count`.
+/// Buffered clicks live in Redis, so they survive restarts of this service.
+pub struct ClickCounter {
+    client: Client,
+    /// Identifies this replica as the lock holder
+    instance_id: String,
+}
+
+impl ClickCounter {
+    pub fn new(redis_url: &str) -> RedisResult<Self> {
+        Ok(Self {
+            client: Client::open(redis_url)?,
+            instance_id: uuid::Uuid::new_v4().to_string(),
+        })
+    }
+
+    /// Count one redirect for `short_code` and add the visitor to today's unique visitor estimate
+    pub async fn record(&self, short_code: &str, visitor: &str) -> RedisResult<()> {
+        let uniques_key = unique_visitors_key(short_code, chrono::Utc::now().date_naive());
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+        redis::pipe()
+            .hincr(PENDING_KEY, short_code, 1)
+            .ignore()
+            .pfadd(&uniques_key, visitor)
+            .ignore()
+            .expire(&uniques_key, UNIQUE_VISITORS_TTL_SECS)
+            .ignore()
+            .query_async(&mut conn)
+            .await
+    }
+
+    /// Flush buffered clicks every `interval`. The first run happens right away,
+    /// which picks up a snapshot left behind by a crash during a previous flush.
+    pub fn spawn_flusher(self: Arc<Self>, db_pool: DbPool, interval: Duration) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match self.flush(&db_pool).await {
+                    Ok(0) => {}
+                    Ok(flushed) => info!("🖱️ Flushed click counts for {} links", flushed),
+                    Err(err) => warn!("⚠️ Failed to flush click counts, will retry: {}", err),
+                }
+            }
+        });
+    }
+
+    /// Returns the number of links whose counts were written
+    async fn flush(&self, db_pool: &DbPool) -> Result<usize, FlushError> {
+        let mut conn = self.client.get_multiplexed_async_connection().await?;
+
+        let locked: Option<String> = redis::cmd("SET")
+            .arg(LOCK_KEY)
+            .arg(&self.instance_id)
+            .arg("NX")
+            .arg("EX")
+            .arg(LOCK_TTL_SECS)
+            .query_async(&mut conn)
+            .await?;
+        if locked.is_none() {
+            return Ok(0); // Another replica is flushing
+        }
+
+        let result = self.flush_locked(&mut conn, db_pool).await;
+
+        let released: RedisResult<i32> = redis::Script::new(RELEASE_LOCK_SCRIPT)
+            .key(LOCK_KEY)
+            .arg(&self.instance_id)
+            .invoke_async(&mut conn)
+            .await;
+        if let Err(err) = released {
+            warn!("⚠️ Failed to release click flush lock: {}", err);
+        }
+
+        result
+    }
+
+    async fn flush_locked(
+        &self,
+        conn: &mut redis::aio::MultiplexedConnection,
+        db_pool: &DbPool,
+    ) -> Result<usize, FlushError> {
+        // A leftover snapshot means the last flush never confirmed; write it before taking a new one
+        let leftover: bool = conn.exists(FLUSHING_KEY).await?;
+        if !leftover {
+            // RENAME fails on a missing key; nothing else removes PENDING_KEY while we hold the lock
+            let pending: bool = conn.exists(PENDING_KEY).await?;
+            if !pending {
+                return Ok(0);
+            }
+            conn.rename::<_, _, ()>(PENDING_KEY, FLUSHING_KEY).await?;
+        }
+
+        let counts: HashMap<String, i64> = conn.hgetall(FLUSHING_KEY).await?;
+        let flushed = counts.len();
+
+        let db_pool = db_pool.clone();
+        tokio::task::spawn_blocking(move || apply_counts(&db_pool, &counts)).await??;
+
+        // A crash before this DEL replays the snapshot on the next run, so counts are
+        // never lost but may be applied twice in that narrow window
+        conn.del::<_, ()>(FLUSHING_KEY).await?;
+        Ok(flushed)
+    }
+}
+
+/// Add every buffered count in a single transaction
+fn apply_counts(db_pool: &DbPool, counts: &HashMap<String, i64>) -> Result<(), FlushError> {
+    let mut conn = db_pool.get()?;
+    conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        for (code, clicks) in counts {
+            let clicks = i32::try_from(*clicks).unwrap_or(i32::MAX);
+            diesel::update(url_mapping.find(code))
+                .set(click_count.eq(click_count + clicks))
+                .execute(conn)?;
+        }
+        Ok(())
+    })?;
+    Ok(())
+}
@@ -0,0 +1,62 @@
+use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::proxies::TrustedProxies;
+
+const DEFAULT_CLICK_FLUSH_SECS: u64 = 10;
+const DEFAULT_CLICK_EVENT_BUFFER: usize = 10_000;
+const DEFAULT_GEOIP_RELOAD_SECS: u64 = 300;
+
+/// Service settings read from the environment once at startup
+pub struct Config {
+    /// How often buffered clicks are written to `url_mapping.click_count`
+    pub click_flush_interval: Duration,
+    /// Click events waiting to be published; once full, further events are dropped
+    pub click_event_buffer: usize,
+    /// Salt for visitor IP hashes; `None` means a random one per process
+    pub click_ip_salt: Option<String>,
+    /// Local MaxMind (`.mmdb`) database used to locate clicks
+    pub geoip_db_path: Option<PathBuf>,
+    pub geoip_reload_interval: Duration,
+    /// Peers whose `X-Forwarded-For` header is believed
+    pub trusted_proxies: TrustedProxies,
+}
+
+/// Splits a comma-separated variable into trimmed, non-empty entries
+fn list_var(name: &str) -> Vec<String> {
+    env::var(name)
+        .unwrap_or_default()
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// A positive number from the environment, or `default` when unset
+fn positive_var<T: std::str::FromStr + PartialOrd + Default>(name: &str, default: T) -> T {
+    match env::var(name) {
+        Ok(value) => value
+            .parse::<T>()
+            .ok()
+            .filter(|parsed| *parsed > T::default())
+            .unwrap_or_else(|| panic!("{} must be a positive number", name)),
+        Err(_) => default,
+    }
+}
+
+impl Config {
+    pub fn from_env() -> Self {
+        let trusted_proxies = TrustedProxies::parse(&list_var("TRUSTED_PROXIES"))
+            .unwrap_or_else(|err| panic!("Invalid TRUSTED_PROXIES: {}", err));
+
+        Config {
+            click_flush_interval: Duration::from_secs(positive_var("CLICK_FLUSH_INTERVAL_SECS", DEFAULT_CLICK_FLUSH_SECS)),
+            click_event_buffer: positive_var("CLICK_EVENT_BUFFER", DEFAULT_CLICK_EVENT_BUFFER),
+            click_ip_salt: env::var("CLICK_IP_SALT").ok().filter(|salt| !salt.is_empty()),
+            geoip_db_path: env::var("GEOIP_DB_PATH").ok().filter(|path| !path.is_empty()).map(PathBuf::from),
+            geoip_reload_interval: Duration::from_secs(positive_var("GEOIP_RELOAD_SECS", DEFAULT_GEOIP_RELOAD_SECS)),
+            trusted_proxies,
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use std::net::IpAddr;
+
+use common::events::{ClickEvent, CLICK_EVENTS_QUEUE};
+use std::sync::Arc;
+
+use common::rabbitmq::AmqpManager;
+use lapin::{options::*, types::FieldTable, BasicProperties, Channel};
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tracing::{info, warn};
+
+/// Hands click events to a background publisher without ever waiting on RabbitMQ.
+/// When the buffer is full, new events are dropped instead of slowing down redirects.
+pub struct ClickEmitter {
+    sender: mpsc::Sender<ClickEvent>,
+    ip_salt: String,
+}
+
+impl ClickEmitter {
+    /// Starts the publisher task; `buffer` is the number of events held while RabbitMQ is slow
+    pub fn spawn(amqp: Arc<AmqpManager>, buffer: usize, ip_salt: String) -> Self {
+        let (sender, receiver) = mpsc::channel(buffer);
+        tokio::spawn(publish_events(amqp, receiver));
+        ClickEmitter { sender, ip_salt }
+    }
+
+    pub fn emit(&self, event: ClickEvent) {
+        match self.sender.try_send(event) {
+            Ok(()) => {}
+            Err(TrySendError::Full(event)) => {
+                warn!("⚠️ Click event buffer is full, dropping event for {}", event.short_url);
+            }
+            Err(TrySendError::Closed(_)) => {
+                warn!("⚠️ Click event publisher has stopped, dropping event");
+            }
+        }
+    }
+
+    /// Salted SHA-256 of the client address, so unique visitors can be told apart without storing IPs
+    pub fn hash_ip(&self, ip: IpAddr) -> String {
+        hex_digest(&format!("{}{}", self.ip_salt, ip))
+    }
+
+    /// Salted hash of address and user agent, which tells apart visitors sharing one address (NAT)
+    pub fn visitor_fingerprint(&self, ip: IpAddr, user_agent: Option<&str>) -> String {
+        hex_digest(&format!("{}{}|{}", self.ip_salt, ip, user_agent.unwrap_or_default()))
+    }
+}
+
+fn hex_digest(input: &str) -> String {
+    let digest = Sha256::digest(input.as_bytes());
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+async fn open_channel(amqp: &AmqpManager) -> Result<Channel, lapin::Error> {
+    let channel = amqp.create_channel().await?;
+    channel
+        .queue_declare(
+            CLICK_EVENTS_QUEUE,
+            QueueDeclareOptions {
+                durable: true,
+                ..QueueDeclareOptions::default()
+            },
+            FieldTable::default(),
+        )
+        .await?;
+    info!("✅ Queue declared: {}", CLICK_EVENTS_QUEUE);
+    Ok(channel)
+}
+
+async fn publish_events(amqp: Arc<AmqpManager>, mut receiver: mpsc::Receiver<ClickEvent>) {
+    let mut channel: Option<Channel> = None;
+
+    while let Some(event) = receiver.recv().await {
+        if channel.as_ref().is_none_or(|channel| !channel.status().connected()) {
+            // Fails right away while disconnected, so the buffer does not back up behind an outage
+            channel = match open_channel(&amqp).await {
+                Ok(channel) => Some(channel),
+                Err(err) => {
+                    warn!("⚠️ Failed to open click event channel, dropping event: {}", err);
+                    continue;
+                }
+            };
+        }
+        let Some(open) = &channel else { continue };
+
+        let payload = serde_json::to_vec(&event).expect("ClickEvent always serializes");
+        let published = open
+            .basic_publish(
+                "",
+                CLICK_EVENTS_QUEUE,
+                BasicPublishOptions::default(),
+                &payload,
+                BasicProperties::default().with_delivery_mode(2), // persistent
+            )
+            .await;
+
+        if let Err(err) = published {
+            warn!("⚠️ Failed to publish click event, dropping it: {}", err);
+            channel = None; // Reconnect on the next event
+        }
+    }
+}
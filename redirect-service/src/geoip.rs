@@ -0,0 +1,92 @@
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use maxminddb::{geoip2, Reader};
+use tracing::{info, warn};
+
+/// Where a click came from, as far as the GeoIP database knows
+#[derive(Default)]
+pub struct Location {
+    /// ISO 3166-1 alpha-2 code
+    pub country: Option<String>,
+    /// English city name
+    pub city: Option<String>,
+}
+
+/// Offline lookups in a local MaxMind `.mmdb` file (GeoLite2/GeoIP2 City or Country).
+/// Without a configured file every lookup simply returns an empty location.
+pub struct GeoIp {
+    path: Option<PathBuf>,
+    reader: RwLock<Option<Arc<Reader<Vec<u8>>>>>,
+}
+
+impl GeoIp {
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let geoip = GeoIp {
+            path,
+            reader: RwLock::new(None),
+        };
+        geoip.reload();
+        geoip
+    }
+
+    pub fn locate(&self, ip: IpAddr) -> Location {
+        let Some(reader) = self.reader.read().unwrap().clone() else {
+            return Location::default();
+        };
+
+        let record = reader
+            .lookup(ip)
+            .and_then(|result| result.decode::<geoip2::City>());
+        match record {
+            Ok(Some(record)) => Location {
+                country: record.country.iso_code.map(str::to_string),
+                city: record.city.names.english.map(str::to_string),
+            },
+            // Private and unknown addresses are simply not in the database
+            Ok(None) | Err(_) => Location::default(),
+        }
+    }
+
+    fn reload(&self) {
+        let Some(path) = &self.path else { return };
+
+        match Reader::open_readfile(path) {
+            Ok(reader) => {
+                info!("🌍 Loaded GeoIP database {} ({})", path.display(), reader.metadata().database_type);
+                *self.reader.write().unwrap() = Some(Arc::new(reader));
+            }
+            // Keep serving from the previous copy, if any
+            Err(err) => warn!("⚠️ Failed to read GeoIP database {}: {}", path.display(), err),
+        }
+    }
+
+    fn modified_at(&self) -> Option<SystemTime> {
+        let path = self.path.as_ref()?;
+        std::fs::metadata(path).and_then(|meta| meta.modified()).ok()
+    }
+
+    /// Polls the file's modification time and reloads it whenever it changes
+    pub fn spawn_reloader(self: Arc<Self>, interval: Duration) {
+        if self.path.is_none() {
+            return;
+        }
+
+        tokio::spawn(async move {
+            let mut last_modified = self.modified_at();
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let modified = self.modified_at();
+                if modified != last_modified {
+                    last_modified = modified;
+                    // Databases are tens of megabytes; read them off the async workers
+                    let geoip = self.clone();
+                    let _ = tokio::task::spawn_blocking(move || geoip.reload()).await;
+                }
+            }
+        });
+    }
+}
@@ -1,18 +1,32 @@
+use std::net::SocketAddr;
 use std::sync::Arc;
 use axum::{routing::get, Router};
 use tokio::net::TcpListener;
+use tracing::warn;
 use common::db::{init_pool,DbPool};
+use common::rabbitmq::AmqpManager;
 
 mod rabbitmq;
 mod routes;
 mod cache;
+mod clicks;
+mod config;
+mod events;
+mod geoip;
+mod proxies;
 mod models;
 mod schema;
+mod traffic;
 
 #[derive(Clone)]
 struct AppState {
     db_pool: DbPool,
     cache: Arc<cache::RedisCache>,
+    clicks: Arc<clicks::ClickCounter>,
+    events: Arc<events::ClickEmitter>,
+    geoip: Arc<geoip::GeoIp>,
+    trusted_proxies: proxies::TrustedProxies,
+    amqp: Arc<AmqpManager>,
 }
 
 #[tokio::main]
@@ -20,20 +34,35 @@ async fn main() {
     dotenvy::dotenv().ok();
     let redis_url = std::env::var("REDIS_URL").expect("REDIS_URL must be set");
     let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let _rabbitmq_url = std::env::var("RABBITMQ_URL").expect("RABBITMQ_URL must be set");
+    let rabbitmq_url = std::env::var("RABBITMQ_URL").expect("RABBITMQ_URL must be set");
+    let config = config::Config::from_env();
 
     let db_pool = init_pool(&database_url);
     let cache = Arc::new(cache::RedisCache::new(&redis_url).await.unwrap());
+    let clicks = Arc::new(clicks::ClickCounter::new(&redis_url).expect("Failed to connect to Redis"));
+    clicks.clone().spawn_flusher(db_pool.clone(), config.click_flush_interval);
 
-    let redis_client = redis::Client::open(redis_url).expect("Failed to connect to Redis");
-    rabbitmq::listen_for_updates(redis_client).await;
+    // Connects in the background; redirects are served while RabbitMQ is down
+    let amqp = AmqpManager::spawn(rabbitmq_url);
+    rabbitmq::listen_for_updates(&amqp, cache.clone());
 
-    let state = Arc::new(AppState {db_pool,cache});
+    // Keep the salt stable across restarts and replicas so visitor hashes stay comparable
+    let ip_salt = config.click_ip_salt.unwrap_or_else(|| {
+        warn!("⚠️ CLICK_IP_SALT is not set, using a random salt; visitor hashes will change on restart");
+        uuid::Uuid::new_v4().to_string()
+    });
+    let events = Arc::new(events::ClickEmitter::spawn(amqp.clone(), config.click_event_buffer, ip_salt));
+
+    let geoip = Arc::new(geoip::GeoIp::load(config.geoip_db_path));
+    geoip.clone().spawn_reloader(config.geoip_reload_interval);
+
+    let state = Arc::new(AppState {db_pool,cache,clicks,events,geoip,trusted_proxies: config.trusted_proxies,amqp});
 
     let app = Router::new()
+        .route("/health", get(routes::health))
         .route("/:short_code", get(routes::redirect))
         .with_state(state.clone()); // Pass database pool
 
     let listener = TcpListener::bind("0.0.0.0:8081").await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap();
 }
@@ -11,4 +11,7 @@ pub struct ShortUrl {
     pub expiration_date: Option<chrono::NaiveDateTime>,
     pub user_id: Option<i32>,
     pub click_count: i32,
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+    pub disabled: bool,
+    pub redirect_type: i32,
 }
\ No newline at end of file
@@ -0,0 +1,83 @@
+use std::net::IpAddr;
+
+use axum::http::HeaderMap;
+
+/// Addresses or CIDR ranges of reverse proxies (such as the api-gateway) allowed to
+/// report the original client through `X-Forwarded-For`
+#[derive(Clone)]
+pub struct TrustedProxies {
+    networks: Vec<(IpAddr, u8)>,
+}
+
+impl TrustedProxies {
+    /// Accepts entries like `172.18.0.5`, `10.0.0.0/8` or `fd00::/8`
+    pub fn parse(entries: &[String]) -> Result<Self, String> {
+        let networks = entries
+            .iter()
+            .map(|entry| {
+                let (address, prefix) = match entry.split_once('/') {
+                    Some((address, prefix)) => (address, Some(prefix)),
+                    None => (entry.as_str(), None),
+                };
+                let address: IpAddr = address
+                    .parse()
+                    .map_err(|_| format!("'{}' is not an IP address", entry))?;
+                let max_prefix = if address.is_ipv4() { 32 } else { 128 };
+                let prefix = match prefix {
+                    Some(prefix) => prefix
+                        .parse::<u8>()
+                        .ok()
+                        .filter(|prefix| *prefix <= max_prefix)
+                        .ok_or_else(|| format!("'{}' has an invalid prefix length", entry))?,
+                    None => max_prefix,
+                };
+                Ok((address, prefix))
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(TrustedProxies { networks })
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.networks.iter().any(|(network, prefix)| match (network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - u32::from(*prefix)).unwrap_or(0);
+                u32::from(*network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - u32::from(*prefix)).unwrap_or(0);
+                u128::from(*network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        })
+    }
+
+    /// The address of the client that started the request. `X-Forwarded-For` is read from
+    /// right to left and only while each hop is a trusted proxy, so clients cannot spoof it.
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        let mut client = peer.to_canonical();
+        if !self.contains(client) {
+            return client;
+        }
+
+        let forwarded = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect::<Vec<_>>();
+
+        for hop in forwarded.into_iter().rev() {
+            let Ok(hop) = hop.parse::<IpAddr>() else {
+                break; // Garbage in the header; keep the last address we could trust
+            };
+            client = hop.to_canonical();
+            if !self.contains(client) {
+                break;
+            }
+        }
+        client
+    }
+}
@@ -1,63 +1,104 @@
-use std::env;
-use lapin::{options::*, types::FieldTable, Consumer};
-use redis::AsyncCommands;
+use std::sync::Arc;
+use lapin::{options::*, types::FieldTable, Channel, Consumer};
 use futures_util::StreamExt;
-use common::rabbitmq::connect_to_rabbitmq;
+use common::events::{LinkEvent, LinkEventKind, LINK_EVENTS_ALL, LINK_EVENTS_EXCHANGE};
+use common::rabbitmq::{declare_link_events_exchange, AmqpManager};
+use redis::RedisResult;
+use tokio::time::{sleep, Duration};
 use tracing::info;
 
-pub async fn listen_for_updates(redis: redis::Client) {
-    let rabbitmq_url = env::var("RABBITMQ_URL").expect("RABBITMQ_URL must be set");
-    
-    let conn= connect_to_rabbitmq(&rabbitmq_url).await;
-    
-    let channel = conn.create_channel().await.expect("Failed to create channel");
-    // ✅ Declare the queue before publishing
-    channel
+use crate::cache::{CachedLink, RedisCache};
+
+/// How long pre-cached links stay in Redis
+const CACHE_TTL_SECS: u64 = 3600;
+/// Pause before an event that could not be applied to Redis is requeued
+const REDIS_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Bring the cache in line with a link event: live links are (re)cached under their code
+/// and alias, anything else is evicted
+async fn apply_link_event(cache: &RedisCache, event: &LinkEvent) -> RedisResult<()> {
+    if event.kind != LinkEventKind::Created {
+        // Covers removed aliases, and updates that leave nothing worth caching (e.g. already expired)
+        cache.delete(&event.cache_keys()).await?;
+    }
+    if matches!(event.kind, LinkEventKind::Created | LinkEventKind::Updated) && !event.link.disabled {
+        let link = CachedLink::from(&event.link);
+        for key in std::iter::once(&event.link.short_url).chain(event.link.alias.as_ref()) {
+            cache.set(key, &link, CACHE_TTL_SECS).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Keep the cache in sync with link events for as long as the service runs, resubscribing after reconnects
+pub fn listen_for_updates(amqp: &Arc<AmqpManager>, cache: Arc<RedisCache>) {
+    amqp.run_consumer("Link event", move |channel| consume_link_events(channel, cache.clone()));
+}
+
+async fn consume_link_events(channel: Channel, cache: Arc<RedisCache>) -> Result<(), lapin::Error> {
+    declare_link_events_exchange(&channel).await?;
+
+    // A server-named queue per replica, so every replica gets every event. It goes away with the
+    // connection, but the cache lives in the shared Redis, so one replica applying an event is
+    // enough; while none is bound the shortener's relay gets its events returned and keeps them.
+    let queue = channel
             .queue_declare(
-                "url_queue",
+                "",
                 QueueDeclareOptions {
-                    passive: false,      // The queue must exist
-                    durable: true,       // The queue will persist across RabbitMQ restarts
-                    auto_delete: false,  // The queue is not deleted when no consumers exist
-                    exclusive: false,    // Allow multiple consumers
-                    nowait: false,
+                    exclusive: true,     // Only this connection consumes from it
+                    auto_delete: true,   // Removed once this replica disconnects
+                    ..QueueDeclareOptions::default()
                 },
                 FieldTable::default(),
             )
-            .await.unwrap();
-    info!("✅ Queue declared: url_queue");
-
-    let consumer: Consumer = channel
-        .basic_consume("url_queue", "consumer", BasicConsumeOptions::default(), FieldTable::default())
-        .await
-        .expect("Failed to create consumer");
-
-    println!("Listening for messages on RabbitMQ queue: url_queue");
-    // Start a separate task to process messages
-    tokio::spawn(async move {
-        let mut conn = redis.get_multiplexed_async_connection().await.expect("Failed to connect to Redis");
-
-        let mut consumer = consumer;
-        while let Some(delivery) = consumer.next().await {
-            if let Ok(delivery) = delivery {
-                if let Ok(msg) = String::from_utf8(delivery.data.clone()) {
-                    let parts: Vec<&str> = msg.split(':').collect();
-                    if parts.len() == 2 {
-                        let short_code = parts[0];
-                        let original_url = parts[1];
-
-                        if let Err(e) = conn.set_ex::<&str, &str, ()>(short_code, original_url, 3600).await {
-                            eprintln!("Failed to cache URL in Redis: {}", e);
-                        } else {
-                            println!("Cached short_code {} -> {}", short_code, original_url);
-                        }
-                    }
+            .await?;
+    channel
+            .queue_bind(
+                queue.name().as_str(),
+                LINK_EVENTS_EXCHANGE,
+                LINK_EVENTS_ALL,
+                QueueBindOptions::default(),
+                FieldTable::default(),
+            )
+            .await?;
+    info!("✅ Queue {} bound to {} ({})", queue.name(), LINK_EVENTS_EXCHANGE, LINK_EVENTS_ALL);
+
+    let mut consumer: Consumer = channel
+        .basic_consume(queue.name().as_str(), "consumer", BasicConsumeOptions::default(), FieldTable::default())
+        .await?;
+
+    println!("Listening for link events on RabbitMQ exchange: {}", LINK_EVENTS_EXCHANGE);
+    // Ends when the connection is lost
+    while let Some(delivery) = consumer.next().await {
+        let delivery = delivery?;
+        let event = match LinkEvent::decode(&delivery.data) {
+            Ok(event) => event,
+            Err(e) => {
+                // Retrying cannot help; reject so a dead-letter exchange (if any) keeps it
+                eprintln!("Rejecting link event: {}", e);
+                if let Err(e) = delivery.reject(BasicRejectOptions { requeue: false }).await {
+                    eprintln!("Failed to reject message: {}", e);
                 }
+                continue;
+            }
+        };
 
+        match apply_link_event(&cache, &event).await {
+            Ok(()) => {
+                println!("Applied {:?} event for {}", event.kind, event.link.short_url);
                 if let Err(e) = delivery.ack(BasicAckOptions::default()).await {
                     eprintln!("Failed to acknowledge message: {}", e);
                 }
             }
+            Err(e) => {
+                // Acking would lose the event for good; requeue it once Redis had a moment to recover
+                eprintln!("Failed to update Redis for {}, requeueing: {}", event.link.short_url, e);
+                sleep(REDIS_RETRY_DELAY).await;
+                if let Err(e) = delivery.nack(BasicNackOptions { requeue: true, ..BasicNackOptions::default() }).await {
+                    eprintln!("Failed to requeue message: {}", e);
+                }
+            }
         }
-    });
+    }
+    Ok(())
 }
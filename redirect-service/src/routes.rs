@@ -1,13 +1,25 @@
 use crate::AppState;
 use crate::{models::ShortUrl, schema::url_mapping::dsl::*};
 use axum::{
-    extract::{Path, State},
-    response::{Redirect, IntoResponse, Json},
-    http::StatusCode,
+    extract::{ConnectInfo, Path, RawQuery, State},
+    response::{IntoResponse, Json, Response},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
 };
+use common::events::{ClickEvent, TrafficClass};
 use diesel::prelude::*;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 
+use crate::cache::CachedLink;
+use crate::traffic;
+
+/// Upper bound for how long a resolved link stays in Redis
+const CACHE_TTL_SECS: u64 = 3600;
+/// Used when a link (or an old cache entry) carries no usable redirect status
+const DEFAULT_REDIRECT_TYPE: u16 = 302;
+/// Longest header value kept in a click event; longer values are cut
+const MAX_EVENT_HEADER_LENGTH: usize = 512;
+
 
 // Custom error response for 404
 #[derive(serde::Serialize)]
@@ -21,33 +33,88 @@ struct UrlResponse {
     short_url: String,
     alias: Option<String>, // Include the alias field
     long_url: String,
+    redirect_type: i32,
+    expiration_date: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+/// Liveness plus the RabbitMQ connection state; "health" is a reserved alias, so it never shadows a link
+pub async fn health(State(state): State<Arc<AppState>>) -> Json<common::rabbitmq::Health> {
+    Json(state.amqp.health())
+}
+
+/// Redirect to the link's destination with its configured status.
+/// Clients sending `Accept: application/json` or a `?info` query get a JSON description instead.
+/// `HEAD` is answered by the same handler, without a body.
 pub async fn redirect(
+    method: Method,
+    ConnectInfo(client): ConnectInfo<SocketAddr>,
     Path(other_short_code): Path<String>,
+    RawQuery(query): RawQuery,
+    headers: HeaderMap,
     State(state): State<Arc<AppState>>,
-) -> impl IntoResponse {
+) -> Response {
+
+    let now = chrono::Utc::now();
+    let wants_info = wants_info(query.as_deref(), &headers);
 
-    if let Some(cached_url) = state.cache.get(&other_short_code).await {
-        return Redirect::temporary(&cached_url).into_response();
+    // The cache only knows the destination, so descriptions always come from the database
+    let cached = if wants_info { None } else { state.cache.get(&other_short_code).await };
+    if let Some(cached) = cached {
+        if cached.is_expired(now.timestamp()) {
+            let _ = state.cache.delete(std::slice::from_ref(&other_short_code)).await;
+            return expired_response(&other_short_code);
+        }
+        count_click(&state, &method, &headers, client.ip(), &cached.short_url);
+        return redirect_response(cached.redirect_type, &cached.long_url);
     }
 
     let conn = &mut state.db_pool.get().expect("Failed to get DB connection");
 
+    // Codes and aliases share one namespace; both columns are indexed (primary key and unique)
     let record = url_mapping
-        .filter(short_url.eq(&other_short_code))
+        .filter(short_url.eq(&other_short_code).or(alias.eq(&other_short_code)))
         .select(ShortUrl::as_select()) // Now works because `Selectable` is derived
         .first::<ShortUrl>(conn)
         .optional();
 
     match record {
+        Ok(Some(record)) if record.deleted_at.is_some() || record.disabled => {
+            // Deleted and paused links are never cached, so they always end up here
+            let error_response = ErrorResponse {
+                error: "Gone".to_string(),
+                message: format!("The link {} is no longer available", other_short_code),
+            };
+            (StatusCode::GONE, Json(error_response)).into_response()
+        }
+        Ok(Some(record)) if record.expiration_date.is_some_and(|at| at <= now.naive_utc()) => {
+            expired_response(&other_short_code)
+        }
         Ok(Some(record)) => {
-            let _ = state.cache.set(&record.short_url, &record.long_url, 3600).await;
-                        // Return the URL details as JSON
+            let status = u16::try_from(record.redirect_type).unwrap_or(DEFAULT_REDIRECT_TYPE);
+            let link = CachedLink {
+                short_url: record.short_url.clone(),
+                long_url: record.long_url.clone(),
+                expires_at: record.expiration_date.map(|at| at.and_utc().timestamp()),
+                redirect_type: status,
+            };
+            // Cache under both keys; shortener-service invalidates them together
+            let _ = state.cache.set(&record.short_url, &link, CACHE_TTL_SECS).await;
+            if let Some(vanity) = &record.alias {
+                let _ = state.cache.set(vanity, &link, CACHE_TTL_SECS).await;
+            }
+
+            if !wants_info {
+                count_click(&state, &method, &headers, client.ip(), &record.short_url);
+                return redirect_response(status, &record.long_url);
+            }
+
+            // Return the URL details as JSON
             let response = UrlResponse {
                 short_url: record.short_url,
                 alias: record.alias, // Include the alias
                 long_url: record.long_url,
+                redirect_type: record.redirect_type,
+                expiration_date: record.expiration_date.map(|at| at.and_utc()),
             };
 
             (StatusCode::OK, Json(response)).into_response()
@@ -68,3 +135,93 @@ pub async fn redirect(
         }
     }
 }
+
+/// Emit the analytics event for a redirect actually followed; only human clicks are added to
+/// `click_count`, and `HEAD` probes are ignored. Nothing here waits, so redirects are never delayed.
+fn count_click(state: &AppState, method: &Method, headers: &HeaderMap, peer: IpAddr, code: &str) {
+    if method != Method::GET {
+        return;
+    }
+
+    // The raw address only lives for this lookup; events carry its hash and location
+    let client_ip = state.trusted_proxies.client_ip(peer, headers);
+    let location = state.geoip.locate(client_ip);
+    let traffic_class = traffic::classify(headers);
+
+    state.events.emit(ClickEvent {
+        short_url: code.to_string(),
+        clicked_at: chrono::Utc::now(),
+        referrer: event_header(headers, header::REFERER),
+        user_agent: event_header(headers, header::USER_AGENT),
+        accept_language: event_header(headers, header::ACCEPT_LANGUAGE),
+        ip_hash: Some(state.events.hash_ip(client_ip)),
+        country: location.country,
+        city: location.city,
+        traffic_class,
+    });
+
+    if traffic_class != TrafficClass::Human {
+        return;
+    }
+
+    let user_agent = headers.get(header::USER_AGENT).and_then(|agent| agent.to_str().ok());
+    let visitor = state.events.visitor_fingerprint(client_ip, user_agent);
+    let clicks = state.clicks.clone();
+    let code = code.to_string();
+    tokio::spawn(async move {
+        if let Err(err) = clicks.record(&code, &visitor).await {
+            eprintln!("Failed to record click for {}: {}", code, err);
+        }
+    });
+}
+
+fn event_header(headers: &HeaderMap, name: header::HeaderName) -> Option<String> {
+    let value = headers.get(name)?.to_str().ok()?;
+    let end = value
+        .char_indices()
+        .nth(MAX_EVENT_HEADER_LENGTH)
+        .map_or(value.len(), |(index, _)| index);
+    Some(value[..end].to_string())
+}
+
+/// `?info` (with or without a value) or an `Accept` header that lists JSON
+fn wants_info(query: Option<&str>, headers: &HeaderMap) -> bool {
+    let info_query = query.is_some_and(|query| {
+        query.split('&').any(|pair| pair.split('=').next() == Some("info"))
+    });
+    let accepts_json = headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| {
+            accept
+                .split(',')
+                .any(|media| media.split(';').next().unwrap_or_default().trim() == "application/json")
+        });
+    info_query || accepts_json
+}
+
+fn redirect_response(status: u16, location: &str) -> Response {
+    let status = match StatusCode::from_u16(status) {
+        Ok(status) if status.is_redirection() => status,
+        _ => StatusCode::FOUND,
+    };
+
+    match HeaderValue::try_from(location) {
+        Ok(location) => (status, [(header::LOCATION, location)]).into_response(),
+        Err(_) => {
+            let error_response = ErrorResponse {
+                error: "Internal Server Error".to_string(),
+                message: "The link destination cannot be used as a redirect target.".to_string(),
+            };
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)).into_response()
+        }
+    }
+}
+
+fn expired_response(code: &str) -> Response {
+    let error_response = ErrorResponse {
+        error: "Gone".to_string(),
+        message: format!("The link {} has expired", code),
+    };
+    (StatusCode::GONE, Json(error_response)).into_response()
+}
@@ -7,5 +7,8 @@ diesel::table! {
         expiration_date -> Nullable<Timestamp>,
         user_id -> Nullable<Int4>,  
         click_count -> Int4,
+        deleted_at -> Nullable<Timestamp>,
+        disabled -> Bool,
+        redirect_type -> Int4,
     }
 }
\ No newline at end of file
@@ -0,0 +1,98 @@
+use axum::http::{header, HeaderMap};
+use common::events::TrafficClass;
+
+/// User-agent fragments of services that fetch a link to render a preview card
+const PREVIEW_AGENTS: &[&str] = &[
+    "slackbot-linkexpanding",
+    "slack-imgproxy",
+    "twitterbot",
+    "facebookexternalhit", // also sent by iMessage
+    "facebot",
+    "linkedinbot",
+    "whatsapp",
+    "telegrambot",
+    "discordbot",
+    "skypeuripreview",
+    "microsoft teams",
+    "redditbot",
+    "pinterestbot",
+    "embedly",
+    "vkshare",
+    "viber",
+];
+
+/// User-agent fragments of crawlers, monitors and HTTP libraries. Generic words are matched with
+/// the punctuation around a product token (`Googlebot/2.1`, `compatible; SomeBot;`) so they do
+/// not hit device or app names that merely contain them.
+const BOT_AGENTS: &[&str] = &[
+    "bot/",
+    "bot;",
+    "bot)",
+    "bot-",
+    "-bot",
+    "crawler",
+    "spider",
+    "slurp",
+    "curl",
+    "wget",
+    "httpie",
+    "python-requests",
+    "python-urllib",
+    "aiohttp",
+    "go-http-client",
+    "java/",
+    "okhttp",
+    "apache-httpclient",
+    "libwww",
+    "axios",
+    "node-fetch",
+    "headlesschrome",
+    "phantomjs",
+    "lighthouse",
+    "uptime",
+    "pingdom",
+    "statuscake",
+    "site24x7",
+    "newrelicpinger",
+    "datadogsynthetics",
+    "hetrixtools",
+    "check_http",
+];
+
+/// Shortest user agent a real browser plausibly sends
+const MIN_BROWSER_AGENT_LENGTH: usize = 20;
+
+/// Tag a request as a human click, a bot or a link preview from its headers
+pub fn classify(headers: &HeaderMap) -> TrafficClass {
+    // Browsers mark speculative prefetches and prerenders explicitly
+    let purpose = ["purpose", "sec-purpose", "x-purpose", "x-moz"]
+        .iter()
+        .filter_map(|name| headers.get(*name)?.to_str().ok())
+        .any(|value| {
+            let value = value.to_ascii_lowercase();
+            value.contains("preview") || value.contains("prefetch")
+        });
+    if purpose {
+        return TrafficClass::Preview;
+    }
+
+    let Some(agent) = headers.get(header::USER_AGENT).and_then(|agent| agent.to_str().ok()) else {
+        return TrafficClass::Bot;
+    };
+    let agent = agent.to_ascii_lowercase();
+
+    // Unfurlers often also say "bot", so they are matched first
+    if PREVIEW_AGENTS.iter().any(|fragment| agent.contains(fragment)) {
+        return TrafficClass::Preview;
+    }
+    if BOT_AGENTS.iter().any(|fragment| agent.contains(fragment)) {
+        return TrafficClass::Bot;
+    }
+
+    // Every mainstream browser sends a long user agent and an Accept header
+    if agent.len() < MIN_BROWSER_AGENT_LENGTH || !headers.contains_key(header::ACCEPT) {
+        return TrafficClass::Bot;
+    }
+
+    TrafficClass::Human
+}
@@ -0,0 +1,29 @@
+/// Shortest and longest custom alias we accept
+const MIN_ALIAS_LENGTH: usize = 3;
+const MAX_ALIAS_LENGTH: usize = 32;
+
+/// Aliases that would shadow service routes or look like official pages
+const RESERVED_ALIASES: &[&str] = &[
+    "admin", "api", "api-docs", "auth", "dashboard", "health", "help", "links", "login",
+    "logout", "lookup", "r", "register", "settings", "shorten", "static", "stats", "users",
+];
+
+/// Checks a requested custom alias against the allowed charset, length and reserved words.
+pub fn validate_alias(alias: &str) -> Result<(), String> {
+    if alias.len() < MIN_ALIAS_LENGTH || alias.len() > MAX_ALIAS_LENGTH {
+        return Err(format!(
+            "Alias must be between {} and {} characters long",
+            MIN_ALIAS_LENGTH, MAX_ALIAS_LENGTH
+        ));
+    }
+
+    if !alias.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err("Alias may only contain letters, digits, '-' and '_'".to_string());
+    }
+
+    if RESERVED_ALIASES.contains(&alias.to_ascii_lowercase().as_str()) {
+        return Err(format!("Alias '{}' is reserved", alias));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use diesel::prelude::*;
+use diesel::sql_types::Text;
+
+use crate::error::ShortenerError;
+use crate::hashcode::{CodeGenerator, CodeInput};
+use crate::schema::url_mapping::dsl::*;
+
+/// Postgres sequence backing short code allocation (created in `common::db::run_migrations`)
+const CODE_SEQUENCE: &str = "url_code_seq";
+
+/// Upper bound on candidates that collide with an existing alias before giving up
+const MAX_ALIAS_CLASHES: u32 = 10;
+
+define_sql_function! {
+    fn nextval(sequence: Text) -> BigInt;
+}
+
+/// Asks `generator` for a candidate short code without scanning `url_mapping`.
+/// Candidates equal to an existing custom alias are skipped; collisions with other codes
+/// are left to the primary key, and callers retry with a higher `attempt`.
+/// Fails with `CodeSpaceExhausted` once the sequence ran out or too many candidates clashed.
+pub fn allocate_short_code(
+    conn: &mut PgConnection,
+    generator: &dyn CodeGenerator,
+    original_url: &str,
+    attempt: u32,
+) -> Result<String, ShortenerError> {
+    for attempt in attempt..attempt + MAX_ALIAS_CLASHES {
+        // `nextval` is atomic across connections, so sequential codes never repeat
+        let sequence = if generator.needs_sequence() {
+            Some(next_sequence_value(conn)?)
+        } else {
+            None
+        };
+
+        let code = generator.generate(&CodeInput {
+            long_url: original_url,
+            sequence,
+            attempt,
+        });
+
+        let clashes_with_alias = diesel::select(diesel::dsl::exists(url_mapping.filter(alias.eq(&code))))
+            .get_result::<bool>(conn)?;
+
+        if !clashes_with_alias {
+            return Ok(code);
+        }
+    }
+
+    Err(ShortenerError::CodeSpaceExhausted(format!(
+        "{} consecutive candidates clashed with existing aliases",
+        MAX_ALIAS_CLASHES
+    )))
+}
+
+#[derive(QueryableByName)]
+struct SequenceState {
+    #[diesel(sql_type = diesel::sql_types::Bool)]
+    exhausted: bool,
+}
+
+fn next_sequence_value(conn: &mut PgConnection) -> Result<u64, ShortenerError> {
+    match diesel::select(nextval(CODE_SEQUENCE)).get_result::<i64>(conn) {
+        Ok(value) => Ok(value as u64),
+        // Running past MAXVALUE (SQLSTATE 2200H) has no DatabaseErrorKind of its own and its message
+        // depends on the server locale, so ask the catalog whether the sequence is used up instead
+        Err(err @ diesel::result::Error::DatabaseError(..)) => {
+            if sequence_exhausted(conn)? {
+                Err(ShortenerError::CodeSpaceExhausted(format!("{} reached its MAXVALUE", CODE_SEQUENCE)))
+            } else {
+                Err(err.into())
+            }
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn sequence_exhausted(conn: &mut PgConnection) -> QueryResult<bool> {
+    let state: Option<SequenceState> = diesel::sql_query(
+        "SELECT COALESCE(last_value >= max_value, FALSE) AS exhausted FROM pg_sequences \
+         WHERE schemaname = current_schema() AND sequencename = $1",
+    )
+    .bind::<Text, _>(CODE_SEQUENCE)
+    .get_result(conn)
+    .optional()?;
+    Ok(state.is_some_and(|state| state.exhausted))
+}
@@ -0,0 +1,44 @@
+use url::Url;
+
+/// Canonical form of a destination, used to recognise the same link submitted twice.
+///
+/// Scheme and host are lowercased, IDN hosts become punycode and default ports are
+/// dropped (the `url` parser already does this for http/https), query parameters are
+/// sorted by name and tracking parameters are removed.
+pub fn canonicalize(url: &Url, tracking_params: &[String]) -> String {
+    let mut canonical = url.clone();
+
+    if let Some(host) = url.host_str() {
+        let host = host.to_ascii_lowercase();
+        // Only fails for cannot-be-a-base URLs, which have no host to normalise
+        let _ = canonical.set_host(Some(&host));
+    }
+    if canonical.port() == canonical.port_or_known_default() {
+        let _ = canonical.set_port(None);
+    }
+
+    let mut params: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(name, _)| !is_tracking_param(name, tracking_params))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    // Stable sort keeps repeated parameters in their original relative order
+    params.sort_by(|a, b| a.0.cmp(&b.0));
+
+    if params.is_empty() {
+        canonical.set_query(None);
+    } else {
+        canonical.query_pairs_mut().clear().extend_pairs(params);
+    }
+
+    canonical.to_string()
+}
+
+/// Entries ending in `*` match any parameter with that prefix, e.g. `utm_*`
+fn is_tracking_param(name: &str, tracking_params: &[String]) -> bool {
+    let name = name.to_ascii_lowercase();
+    tracking_params.iter().any(|pattern| match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name == *pattern,
+    })
+}
@@ -0,0 +1,114 @@
+use std::env;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::hashcode::{validate_alphabet, CodeStrategy, MAX_CODE_LENGTH, UNAMBIGUOUS_CHARSET};
+
+const BASE62_ALPHABET: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const DEFAULT_CODE_MIN_LENGTH: usize = 7;
+const MIN_CODE_LENGTH: usize = 4;
+
+/// `url_mapping.long_url` is a VARCHAR(1000)
+const DEFAULT_MAX_URL_LENGTH: usize = 1000;
+const DEFAULT_BLOCKLIST_RELOAD_SECS: u64 = 30;
+const DEFAULT_BATCH_MAX_ITEMS: usize = 1000;
+const DEFAULT_RESTORE_WINDOW_DAYS: i64 = 30;
+const DEFAULT_TRACKING_PARAMS: &str =
+    "utm_*,fbclid,gclid,dclid,gbraid,wbraid,msclkid,mc_cid,mc_eid,igshid,yclid,_ga,_gl,ref_src";
+const DEFAULT_SHORTENER_DOMAINS: &str =
+    "bit.ly,buff.ly,cutt.ly,goo.gl,is.gd,ow.ly,rebrand.ly,shorturl.at,t.co,t.ly,tiny.cc,tinyurl.com";
+
+/// Service settings read from the environment once at startup
+pub struct Config {
+    pub code_strategy: CodeStrategy,
+    pub code_alphabet: String,
+    pub code_min_length: usize,
+    pub allowed_schemes: Vec<String>,
+    pub max_url_length: usize,
+    pub own_domains: Vec<String>,
+    pub shortener_domains: Vec<String>,
+    pub blocklist_path: Option<PathBuf>,
+    pub blocklist_reload_interval: Duration,
+    pub tracking_params: Vec<String>,
+    pub batch_max_items: usize,
+    /// How long a soft-deleted link can still be restored
+    pub restore_window: chrono::Duration,
+}
+
+/// Splits a comma-separated variable into trimmed, lowercase, non-empty entries
+fn list_var(name: &str, default: &str) -> Vec<String> {
+    env::var(name)
+        .unwrap_or_else(|_| default.to_string())
+        .split(',')
+        .map(|item| item.trim().to_ascii_lowercase())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// A positive number from the environment, or `default` when unset
+fn positive_var<T: std::str::FromStr + PartialOrd + Default>(name: &str, default: T) -> T {
+    match env::var(name) {
+        Ok(value) => value
+            .parse::<T>()
+            .ok()
+            .filter(|parsed| *parsed > T::default())
+            .unwrap_or_else(|| panic!("{} must be a positive number", name)),
+        Err(_) => default,
+    }
+}
+
+impl Config {
+    pub fn from_env() -> Self {
+        let code_strategy = env::var("CODE_STRATEGY")
+            .map(|value| CodeStrategy::parse(&value).expect("CODE_STRATEGY must be hash, random, sequential or words"))
+            .unwrap_or(CodeStrategy::Sequential);
+
+        // Either a named preset or a literal list of characters
+        let code_alphabet = match env::var("CODE_ALPHABET").as_deref() {
+            Err(_) | Ok("base62") => BASE62_ALPHABET.to_string(),
+            Ok("unambiguous") => UNAMBIGUOUS_CHARSET.to_string(),
+            Ok(custom) => custom.to_string(),
+        };
+        if let Err(err) = validate_alphabet(&code_alphabet) {
+            panic!("Invalid CODE_ALPHABET: {}", err);
+        }
+
+        let code_min_length = env::var("CODE_MIN_LENGTH")
+            .map(|value| value.parse::<usize>().expect("CODE_MIN_LENGTH must be a number"))
+            .unwrap_or(DEFAULT_CODE_MIN_LENGTH);
+        if !(MIN_CODE_LENGTH..=MAX_CODE_LENGTH).contains(&code_min_length) {
+            panic!("CODE_MIN_LENGTH must be between {} and {}", MIN_CODE_LENGTH, MAX_CODE_LENGTH);
+        }
+
+        let max_url_length = env::var("MAX_URL_LENGTH")
+            .map(|value| value.parse::<usize>().expect("MAX_URL_LENGTH must be a number"))
+            .unwrap_or(DEFAULT_MAX_URL_LENGTH)
+            .min(DEFAULT_MAX_URL_LENGTH);
+
+        // A zero interval would make the reloader's `tokio::time::interval` panic
+        let blocklist_reload_interval = positive_var("BLOCKLIST_RELOAD_SECS", DEFAULT_BLOCKLIST_RELOAD_SECS);
+
+        let batch_max_items = env::var("BATCH_MAX_ITEMS")
+            .map(|value| value.parse::<usize>().expect("BATCH_MAX_ITEMS must be a number"))
+            .unwrap_or(DEFAULT_BATCH_MAX_ITEMS);
+
+        let restore_window_days = env::var("LINK_RESTORE_WINDOW_DAYS")
+            .map(|value| value.parse::<i64>().expect("LINK_RESTORE_WINDOW_DAYS must be a number"))
+            .unwrap_or(DEFAULT_RESTORE_WINDOW_DAYS);
+
+        Config {
+            code_strategy,
+            code_alphabet,
+            code_min_length,
+            allowed_schemes: list_var("ALLOWED_SCHEMES", "http,https"),
+            max_url_length,
+            own_domains: list_var("OWN_DOMAINS", ""),
+            shortener_domains: list_var("SHORTENER_DOMAINS", DEFAULT_SHORTENER_DOMAINS),
+            blocklist_path: env::var("BLOCKLIST_PATH").ok().map(PathBuf::from),
+            blocklist_reload_interval: Duration::from_secs(blocklist_reload_interval),
+            tracking_params: list_var("TRACKING_PARAMS", DEFAULT_TRACKING_PARAMS),
+            batch_max_items,
+            restore_window: chrono::Duration::days(restore_window_days),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use diesel::r2d2::PoolError;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use serde::Serialize;
+use tracing::error;
+
+/// Errors returned by shortener-service handlers.
+/// Each variant maps to one HTTP status and a stable machine-readable `error` code.
+#[derive(Debug)]
+pub enum ShortenerError {
+    /// The destination is not a parseable absolute URL
+    InvalidUrl(String),
+    /// Any other malformed field (alias, expiration, ...)
+    InvalidRequest(String),
+    AliasTaken(String),
+    /// No link with the given short code
+    LinkNotFound(String),
+    /// The link belongs to another user
+    Forbidden,
+    /// A deleted link can no longer be restored
+    RestoreWindowExpired(String),
+    /// The destination parses but is rejected by the URL policy
+    BlockedUrl(String),
+    /// No unused short code could be allocated: the sequence reached its MAXVALUE, or every
+    /// candidate clashed with an existing alias or code
+    CodeSpaceExhausted(String),
+    DatabaseUnavailable,
+    Internal(String),
+}
+
+/// JSON body sent with every error response
+#[derive(Serialize)]
+pub struct ErrorBody {
+    pub error: &'static str,
+    pub message: String,
+}
+
+impl ShortenerError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ShortenerError::InvalidUrl(_) | ShortenerError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            ShortenerError::AliasTaken(_) => StatusCode::CONFLICT,
+            ShortenerError::LinkNotFound(_) => StatusCode::NOT_FOUND,
+            ShortenerError::Forbidden => StatusCode::FORBIDDEN,
+            ShortenerError::RestoreWindowExpired(_) => StatusCode::GONE,
+            ShortenerError::BlockedUrl(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ShortenerError::CodeSpaceExhausted(_) | ShortenerError::DatabaseUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ShortenerError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn body(&self) -> ErrorBody {
+        let (code, message) = match self {
+            ShortenerError::InvalidUrl(msg) => ("invalid_url", msg.clone()),
+            ShortenerError::InvalidRequest(msg) => ("invalid_request", msg.clone()),
+            ShortenerError::AliasTaken(alias) => ("alias_taken", format!("Alias '{}' is already taken", alias)),
+            ShortenerError::LinkNotFound(code) => ("link_not_found", format!("No link found for short code: {}", code)),
+            ShortenerError::Forbidden => ("forbidden", "You do not own this link".to_string()),
+            ShortenerError::RestoreWindowExpired(code) => (
+                "restore_window_expired",
+                format!("Link {} was deleted too long ago to be restored", code),
+            ),
+            ShortenerError::BlockedUrl(reason) => ("blocked_url", reason.clone()),
+            ShortenerError::CodeSpaceExhausted(reason) => (
+                "code_space_exhausted",
+                format!("No short code is available ({}); try a custom alias or another code strategy", reason),
+            ),
+            ShortenerError::DatabaseUnavailable => ("database_unavailable", "Database is unavailable, try again later".to_string()),
+            // Internal details are logged, not leaked to clients
+            ShortenerError::Internal(_) => ("internal_error", "An internal error occurred".to_string()),
+        };
+        ErrorBody { error: code, message }
+    }
+}
+
+impl IntoResponse for ShortenerError {
+    fn into_response(self) -> Response {
+        match &self {
+            ShortenerError::Internal(detail) => error!("🚨 Internal error: {}", detail),
+            ShortenerError::CodeSpaceExhausted(reason) => error!("🚨 Short code space exhausted: {}", reason),
+            _ => {}
+        }
+        (self.status(), Json(self.body())).into_response()
+    }
+}
+
+impl From<PoolError> for ShortenerError {
+    fn from(err: PoolError) -> Self {
+        error!("🚨 Failed to get DB connection: {}", err);
+        ShortenerError::DatabaseUnavailable
+    }
+}
+
+impl From<DieselError> for ShortenerError {
+    fn from(err: DieselError) -> Self {
+        match err {
+            DieselError::DatabaseError(DatabaseErrorKind::ClosedConnection, _)
+            | DieselError::BrokenTransactionManager => {
+                error!("🚨 Lost DB connection: {}", err);
+                ShortenerError::DatabaseUnavailable
+            }
+            other => ShortenerError::Internal(other.to_string()),
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use axum::{
+    async_trait,
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    Json,
+};
+
+use crate::error::ShortenerError;
+
+/// `axum::Json` whose rejections (malformed JSON, wrongly typed fields, missing content type)
+/// are answered like every other bad request: 400 with an `invalid_request` JSON body
+/// instead of axum's plain-text 4xx.
+pub struct JsonBody<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for JsonBody<T>
+where
+    Json<T>: FromRequest<S, Rejection = JsonRejection>,
+    S: Send + Sync,
+{
+    type Rejection = ShortenerError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|rejection| ShortenerError::InvalidRequest(rejection.body_text()))?;
+        Ok(JsonBody(value))
+    }
+}
@@ -1,36 +1,239 @@
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashSet;
 
-/// Character set for Base62 encoding
-const BASE62_CHARSET: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+/// Base62 without characters that are easily confused when read aloud or printed (0/O, 1/l/I)
+pub const UNAMBIGUOUS_CHARSET: &str = "23456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
 
-/// Converts a decimal number to a Base62 string
-fn base62_encode(mut num: u64) -> String {
-    let mut encoded = String::new();
-    while num > 0 {
-        encoded.push(BASE62_CHARSET[(num % 62) as usize] as char);
-        num /= 62;
+/// Sequential base62 codes have exactly this many characters before padding (fits `VARCHAR(10)`)
+pub const CODE_LENGTH: u32 = 7;
+
+/// Longest code the `url_mapping.short_url` column can hold
+pub const MAX_CODE_LENGTH: usize = 10;
+
+/// Number of distinct codes of `CODE_LENGTH` base62 characters (62^7), which is also
+/// the `MAXVALUE` of `url_code_seq`
+pub const CODE_SPACE: u64 = 62u64.pow(CODE_LENGTH);
+
+/// Odd and not a multiple of 31, so it is coprime with 62^7 and
+/// multiplying by it permutes the code space without collisions
+const SCRAMBLE_MULTIPLIER: u128 = 25_214_903_917;
+
+/// Letters used to build pronounceable consonant-vowel codes
+const CONSONANTS: &[char] = &['b', 'd', 'f', 'g', 'h', 'j', 'k', 'm', 'n', 'p', 'r', 's', 't', 'v', 'z'];
+const VOWELS: &[char] = &['a', 'e', 'i', 'o', 'u'];
+
+/// Characters a sequential code needs so every sequence value gets its own code in a `base`-character alphabet
+fn sequential_digits(base: u128) -> u32 {
+    let mut digits = 1;
+    while base.pow(digits) < CODE_SPACE as u128 {
+        digits += 1;
     }
-    encoded.chars().rev().collect() // Reverse to maintain correct order
+    digits
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    if b == 0 { a } else { gcd(b, a % b) }
 }
 
-/// Generates a unique short code from a long URL using SHA-256 and Base62 encoding
-pub fn generate_short_code(long_url: &str, existing_short_codes: &HashSet<String>) -> String {
-    let hash = Sha256::digest(long_url.as_bytes());
+/// Available short code generation strategies, selectable per request or via `CODE_STRATEGY`
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CodeStrategy {
+    Hash,
+    Random,
+    Sequential,
+    Words,
+}
 
-    let hash_bytes = &hash[0..6];
-    let num = u64::from_be_bytes([
-        0, 0, 0, 0, hash_bytes[0], hash_bytes[1], hash_bytes[2], hash_bytes[3]
-    ]) + u64::from_be_bytes([0, 0, 0, 0, hash_bytes[4], hash_bytes[5], 0, 0]);
+impl CodeStrategy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "hash" => Some(Self::Hash),
+            "random" => Some(Self::Random),
+            "sequential" => Some(Self::Sequential),
+            "words" => Some(Self::Words),
+            _ => None,
+        }
+    }
+}
 
-    let mut short_code = base62_encode(num);
+/// Everything a generator may derive a candidate code from
+pub struct CodeInput<'a> {
+    pub long_url: &'a str,
+    /// Next value of `url_code_seq`, only fetched for generators that ask for it
+    pub sequence: Option<u64>,
+    /// Starts at 0 and grows each time a previous candidate was already taken
+    pub attempt: u32,
+}
 
-    let mut counter = 1;
-    while existing_short_codes.contains(&short_code) {
-        let suffix = base62_encode(counter as u64);
-        short_code = format!("{}{}", short_code, suffix);
-        counter += 1;
+/// A source of candidate short codes. Uniqueness is enforced by the database,
+/// so generators only need to produce a different candidate on each attempt.
+pub trait CodeGenerator: Send + Sync {
+    fn generate(&self, input: &CodeInput) -> String;
+
+    /// Whether `generate` needs `CodeInput::sequence` to be filled in
+    fn needs_sequence(&self) -> bool {
+        false
+    }
+}
+
+/// SHA-256 of the long URL encoded with the configured alphabet.
+/// Retries salt the URL with the attempt number.
+pub struct HashGenerator {
+    alphabet: Vec<char>,
+    length: usize,
+}
+
+impl CodeGenerator for HashGenerator {
+    fn generate(&self, input: &CodeInput) -> String {
+        let hash = if input.attempt == 0 {
+            Sha256::digest(input.long_url.as_bytes())
+        } else {
+            Sha256::digest(format!("{}#{}", input.long_url, input.attempt).as_bytes())
+        };
+
+        let base = self.alphabet.len() as u128;
+        let mut num = u128::from_be_bytes(hash[0..16].try_into().expect("digest is 32 bytes"));
+        let mut code = String::with_capacity(self.length);
+        for _ in 0..self.length {
+            code.push(self.alphabet[(num % base) as usize]);
+            num /= base;
+        }
+        code
+    }
+}
+
+/// Random nanoid over the configured alphabet
+pub struct RandomGenerator {
+    alphabet: Vec<char>,
+    length: usize,
+}
+
+impl CodeGenerator for RandomGenerator {
+    fn generate(&self, _input: &CodeInput) -> String {
+        nanoid::format(nanoid::rngs::default, &self.alphabet, self.length)
+    }
+}
+
+/// Scrambled encoding of `url_code_seq` in the configured alphabet; the only strategy that is
+/// collision-free by construction. Distinct sequence values always map to distinct codes, while
+/// consecutive ones are scattered so codes are not trivially enumerable.
+pub struct SequentialGenerator {
+    alphabet: Vec<char>,
+    /// Fixed width of the encoding, wide enough for every sequence value
+    digits: u32,
+    /// Coprime with `alphabet.len()`, so multiplying by it permutes the `base^digits` code space
+    multiplier: u128,
+    length: usize,
+}
+
+impl SequentialGenerator {
+    fn new(alphabet: Vec<char>, min_length: usize) -> Self {
+        let base = alphabet.len() as u128;
+        let digits = sequential_digits(base);
+        let multiplier = (SCRAMBLE_MULTIPLIER..)
+            .find(|candidate| gcd(*candidate, base) == 1)
+            .expect("some number is coprime with the alphabet size");
+        SequentialGenerator {
+            alphabet,
+            digits,
+            multiplier,
+            length: min_length.max(digits as usize),
+        }
+    }
+}
+
+impl CodeGenerator for SequentialGenerator {
+    fn generate(&self, input: &CodeInput) -> String {
+        let id = input.sequence.expect("sequential generator requires a sequence value");
+        let base = self.alphabet.len() as u128;
+        let mut scrambled = id as u128 * self.multiplier % base.pow(self.digits);
+
+        // Fixed-width, most significant digit first, padded with the alphabet's first character
+        let mut code = vec![self.alphabet[0]; self.length];
+        for slot in code.iter_mut().rev().take(self.digits as usize) {
+            *slot = self.alphabet[(scrambled % base) as usize];
+            scrambled /= base;
+        }
+        code.into_iter().collect()
+    }
+
+    fn needs_sequence(&self) -> bool {
+        true
+    }
+}
+
+/// Pronounceable lowercase codes made of consonant-vowel syllables, e.g. `bakotumi`
+pub struct WordGenerator {
+    length: usize,
+}
+
+impl CodeGenerator for WordGenerator {
+    fn generate(&self, _input: &CodeInput) -> String {
+        let mut rng = rand::thread_rng();
+        (0..self.length)
+            .map(|i| {
+                let letters = if i % 2 == 0 { CONSONANTS } else { VOWELS };
+                *letters.choose(&mut rng).expect("letter sets are not empty")
+            })
+            .collect()
+    }
+}
+
+/// One instance of every strategy, built once from configuration
+pub struct CodeGenerators {
+    pub default_strategy: CodeStrategy,
+    hash: HashGenerator,
+    random: RandomGenerator,
+    sequential: SequentialGenerator,
+    words: WordGenerator,
+}
+
+impl CodeGenerators {
+    /// `alphabet` applies to the hash, random and sequential strategies; `min_length` to all of them
+    pub fn new(default_strategy: CodeStrategy, alphabet: &str, min_length: usize) -> Self {
+        let alphabet: Vec<char> = alphabet.chars().collect();
+        CodeGenerators {
+            default_strategy,
+            hash: HashGenerator { alphabet: alphabet.clone(), length: min_length },
+            random: RandomGenerator { alphabet: alphabet.clone(), length: min_length },
+            sequential: SequentialGenerator::new(alphabet, min_length),
+            words: WordGenerator { length: min_length },
+        }
+    }
+
+    pub fn get(&self, strategy: CodeStrategy) -> &dyn CodeGenerator {
+        match strategy {
+            CodeStrategy::Hash => &self.hash,
+            CodeStrategy::Random => &self.random,
+            CodeStrategy::Sequential => &self.sequential,
+            CodeStrategy::Words => &self.words,
+        }
+    }
+}
+
+/// Checks that a configured alphabet is usable in URLs and large enough to be useful
+pub fn validate_alphabet(alphabet: &str) -> Result<(), String> {
+    if !alphabet.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err("alphabet may only contain letters, digits, '-' and '_'".to_string());
+    }
+
+    let mut unique: Vec<char> = alphabet.chars().collect();
+    unique.sort_unstable();
+    unique.dedup();
+    if unique.len() != alphabet.len() {
+        return Err("alphabet contains duplicate characters".to_string());
+    }
+    if unique.len() < 16 {
+        return Err("alphabet needs at least 16 characters".to_string());
+    }
+    if sequential_digits(unique.len() as u128) as usize > MAX_CODE_LENGTH {
+        return Err(format!(
+            "alphabet is too small for sequential codes of at most {} characters",
+            MAX_CODE_LENGTH
+        ));
     }
 
-    short_code
+    Ok(())
 }
@@ -1,4 +1,4 @@
-use axum::{routing::post, Router};
+use axum::{middleware, routing::{get, patch, post, put}, Router};
 use diesel::prelude::*;
 use diesel::PgConnection;
 use tracing::info;
@@ -7,13 +7,45 @@ use std::sync::Arc;
 use tokio::net::TcpListener;
 use tower_http::trace::{TraceLayer, DefaultOnResponse, DefaultMakeSpan}; 
 use common::logging::init_tracing;
-use common::db::{init_pool,run_migrations};
+use common::db::{init_pool,run_migrations,DbPool};
+use common::auth::{require_service_token, ServiceToken};
+use common::rabbitmq::AmqpManager;
 
 mod models;
 mod routes;
 mod schema;
 mod rabbitmq;
 mod hashcode;
+mod alias;
+mod allocator;
+mod config;
+mod error;
+mod extract;
+mod policy;
+mod canonical;
+mod outbox;
+
+use config::Config;
+use outbox::OutboxRelay;
+use rabbitmq::Publisher;
+use hashcode::CodeGenerators;
+use policy::{Blocklist, UrlPolicy};
+
+pub struct AppState {
+    pub db_pool: DbPool,
+    pub codes: CodeGenerators,
+    pub policy: UrlPolicy,
+    /// Query parameters stripped when canonicalizing destinations
+    pub tracking_params: Vec<String>,
+    /// Largest number of items accepted by `POST /shorten/batch`
+    pub batch_max_items: usize,
+    pub restore_window: chrono::Duration,
+    /// Publishes link events written to the outbox
+    pub outbox: Arc<OutboxRelay>,
+    /// Channel pool on `amqp` used by the outbox relay
+    pub publisher: Publisher,
+    pub amqp: Arc<AmqpManager>,
+}
 
 
 
@@ -30,12 +62,46 @@ async fn main() {
     run_migrations(&mut conn);
     info!("✅ Migrations completed");
     let db_pool = init_pool(&database_url);
+    let config = Config::from_env();
+    let service_token = ServiceToken::from_env();
+
+    let blocklist = Arc::new(Blocklist::load(config.blocklist_path.clone()));
+    blocklist.clone().spawn_reloader(config.blocklist_reload_interval);
+
+    let rabbitmq_url = std::env::var("RABBITMQ_URL").expect("RABBITMQ_URL must be set");
+    // Connects in the background; links are created while RabbitMQ is down and relayed once it is back
+    let amqp = AmqpManager::spawn(rabbitmq_url);
+
+    let state = Arc::new(AppState {
+        db_pool,
+        codes: CodeGenerators::new(config.code_strategy, &config.code_alphabet, config.code_min_length),
+        policy: UrlPolicy {
+            allowed_schemes: config.allowed_schemes,
+            max_length: config.max_url_length,
+            own_domains: config.own_domains,
+            shortener_domains: config.shortener_domains,
+            blocklist,
+        },
+        tracking_params: config.tracking_params,
+        batch_max_items: config.batch_max_items,
+        restore_window: config.restore_window,
+        outbox: OutboxRelay::new(),
+        publisher: Publisher::new(amqp.clone()),
+        amqp,
+    });
+    state.outbox.clone().spawn(state.clone());
 
-    let state = Arc::new(db_pool);
     let app = Router::new()
         .route("/lookup/user/", post(routes::lookup::get_urls_by_user_id))
     
         .route("/shorten", post(routes::urlshort::shorten_url))
+        .route("/shorten/batch", post(routes::batch::shorten_batch))
+        .route("/links/:code", patch(routes::links::update_link).delete(routes::links::delete_link))
+        .route("/links/:code/disabled", put(routes::links::set_link_disabled))
+        .route("/links/:code/restore", post(routes::links::restore_link))
+        // The routes above act for the `user_id` the gateway passes along, so only the gateway may call them
+        .route_layer(middleware::from_fn_with_state(service_token, require_service_token))
+        .route("/health", get(routes::health::health))
         .layer(
             TraceLayer::new_for_http()
             .make_span_with(DefaultMakeSpan::new().level(Level::INFO))
@@ -1,6 +1,7 @@
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 use chrono::NaiveDateTime;
+use common::events::{LinkEvent, LinkEventKind, LinkMetadata};
 use crate::schema::url_mapping;
 
 
@@ -14,4 +15,28 @@ pub struct UrlMappingModel  {
     pub expiration_date: Option<NaiveDateTime>,
     pub user_id: Option<i32>,  
     pub click_count: i32,
-}
\ No newline at end of file
+    pub canonical_url: Option<String>,
+    pub deleted_at: Option<NaiveDateTime>,
+    pub disabled: bool,
+    pub redirect_type: i32,
+}
+
+impl UrlMappingModel {
+    /// Describe this link, as it is now, to other services
+    pub fn event(&self, kind: LinkEventKind) -> LinkEvent {
+        LinkEvent::new(
+            kind,
+            LinkMetadata {
+                short_url: self.short_url.clone(),
+                alias: self.alias.clone(),
+                long_url: self.long_url.clone(),
+                user_id: self.user_id,
+                created_at: self.creation_date.and_utc(),
+                expires_at: self.expiration_date.map(|date| date.and_utc()),
+                // The column only allows 301, 302, 307 and 308
+                redirect_type: u16::try_from(self.redirect_type).unwrap_or(302),
+                disabled: self.disabled,
+            },
+        )
+    }
+}
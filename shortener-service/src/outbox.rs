@@ -0,0 +1,184 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::events::LinkEvent;
+use diesel::prelude::*;
+use diesel::sql_types::{Array, BigInt, Bool, Double, Text};
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+use crate::AppState;
+
+/// Pending events published per relay pass
+const RELAY_BATCH: i64 = 100;
+/// How often the relay looks for events nobody woke it up for, e.g. ones left over from before
+/// a restart or from a pass that failed
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Keeps replicas from claiming rows at the same time
+const RELAY_LOCK_KEY: i64 = 0x6f75_7462_6f78; // "outbox"
+/// How long a relay may wait for the broker to confirm a batch
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long claimed rows stay reserved for the relay publishing them; longer than `PUBLISH_TIMEOUT`,
+/// so a claim only runs out when its relay crashed or lost the database
+const CLAIM_LEASE: Duration = Duration::from_secs(60);
+
+type RelayError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(QueryableByName)]
+struct PendingEvent {
+    #[diesel(sql_type = BigInt)]
+    id: i64,
+    #[diesel(sql_type = Text)]
+    routing_key: String,
+    #[diesel(sql_type = Text)]
+    payload: String,
+}
+
+#[derive(QueryableByName)]
+struct Locked {
+    #[diesel(sql_type = Bool)]
+    locked: bool,
+}
+
+/// Record `event` for publishing. Call it inside the transaction that makes the change,
+/// so the event exists if and only if the change committed.
+pub fn enqueue(conn: &mut PgConnection, event: &LinkEvent) -> QueryResult<()> {
+    let payload = serde_json::to_string(event).expect("LinkEvent always serializes");
+    diesel::sql_query("INSERT INTO outbox (routing_key, payload) VALUES ($1, $2::jsonb)")
+        .bind::<Text, _>(event.kind.routing_key())
+        .bind::<Text, _>(payload)
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Publishes outbox rows to RabbitMQ in insertion order and marks them sent once the broker
+/// confirmed them. A crash between the confirm and marking them sent publishes those rows again
+/// once their claim ran out, so delivery is at-least-once.
+pub struct OutboxRelay {
+    wake: Notify,
+}
+
+impl OutboxRelay {
+    pub fn new() -> Arc<Self> {
+        Arc::new(OutboxRelay { wake: Notify::new() })
+    }
+
+    /// Relay right away instead of at the next poll; call after committing new rows
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+
+    /// Relays through the state's shared publisher, which reconnects on its own after failures
+    pub fn spawn(self: Arc<Self>, state: Arc<AppState>) {
+        tokio::spawn(async move {
+            loop {
+                // Drain everything pending, one batch at a time
+                loop {
+                    match relay_batch(&state).await {
+                        Ok(relayed) if relayed == RELAY_BATCH as usize => continue,
+                        Ok(0) => break,
+                        Ok(relayed) => {
+                            info!("📤 Relayed {} link event(s) from the outbox", relayed);
+                            break;
+                        }
+                        Err(err) => {
+                            warn!("⚠️ Failed to relay outbox events, will retry: {}", err);
+                            break;
+                        }
+                    }
+                }
+
+                tokio::select! {
+                    _ = self.wake.notified() => {}
+                    _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                }
+            }
+        });
+    }
+}
+
+/// Returns the number of events relayed. Rows are claimed and the claim committed before
+/// publishing, so no transaction or row lock is held while waiting for the broker.
+async fn relay_batch(state: &Arc<AppState>) -> Result<usize, RelayError> {
+    let pending = {
+        let state = state.clone();
+        tokio::task::spawn_blocking(move || -> Result<Vec<PendingEvent>, RelayError> {
+            let mut conn = state.db_pool.get()?;
+            Ok(claim_pending(&mut conn)?)
+        })
+        .await??
+    };
+    if pending.is_empty() {
+        return Ok(0);
+    }
+
+    let messages: Vec<(&str, &[u8])> = pending
+        .iter()
+        .map(|event| (event.routing_key.as_str(), event.payload.as_bytes()))
+        .collect();
+    let published = match tokio::time::timeout(PUBLISH_TIMEOUT, state.publisher.publish_confirmed(&messages)).await {
+        Ok(result) => result,
+        Err(_) => Err("Timed out waiting for RabbitMQ to confirm link events".into()),
+    };
+
+    let ids: Vec<i64> = pending.iter().map(|event| event.id).collect();
+    let state = state.clone();
+    tokio::task::spawn_blocking(move || -> Result<usize, RelayError> {
+        let mut conn = state.db_pool.get()?;
+        match published {
+            Ok(()) => {
+                mark_sent(&mut conn, ids)?;
+                Ok(pending.len())
+            }
+            Err(err) => {
+                // Release the claim so the next pass retries these rows without waiting out the lease
+                diesel::sql_query("UPDATE outbox SET claimed_until = NULL WHERE id = ANY($1)")
+                    .bind::<Array<BigInt>, _>(ids)
+                    .execute(&mut conn)?;
+                Err(err)
+            }
+        }
+    })
+    .await?
+}
+
+/// Claims the oldest pending rows for `CLAIM_LEASE`. Nothing is claimed while another relay's claim
+/// is still running, so at most one batch is in flight and events go out in insertion order.
+fn claim_pending(conn: &mut PgConnection) -> QueryResult<Vec<PendingEvent>> {
+    conn.transaction(|conn| {
+        let lock: Locked = diesel::sql_query("SELECT pg_try_advisory_xact_lock($1) AS locked")
+            .bind::<BigInt, _>(RELAY_LOCK_KEY)
+            .get_result(conn)?;
+        if !lock.locked {
+            return Ok(Vec::new()); // Another replica is claiming
+        }
+
+        let mut claimed: Vec<PendingEvent> = diesel::sql_query(
+            "UPDATE outbox SET claimed_until = CURRENT_TIMESTAMP + make_interval(secs => $2) \
+             WHERE id IN ( \
+                 SELECT id FROM outbox WHERE sent_at IS NULL ORDER BY id LIMIT $1 \
+             ) \
+             AND NOT EXISTS ( \
+                 SELECT 1 FROM outbox WHERE sent_at IS NULL AND claimed_until > CURRENT_TIMESTAMP \
+             ) \
+             RETURNING id, routing_key, payload::text AS payload",
+        )
+        .bind::<BigInt, _>(RELAY_BATCH)
+        .bind::<Double, _>(CLAIM_LEASE.as_secs_f64())
+        .load(conn)?;
+        // RETURNING does not keep the subquery's order
+        claimed.sort_by_key(|event| event.id);
+        Ok(claimed)
+    })
+}
+
+fn mark_sent(conn: &mut PgConnection, ids: Vec<i64>) -> QueryResult<()> {
+    conn.transaction(|conn| {
+        diesel::sql_query("UPDATE outbox SET sent_at = CURRENT_TIMESTAMP, claimed_until = NULL WHERE id = ANY($1)")
+            .bind::<Array<BigInt>, _>(ids)
+            .execute(conn)?;
+        // Sent rows are only kept around for troubleshooting
+        diesel::sql_query("DELETE FROM outbox WHERE sent_at < CURRENT_TIMESTAMP - interval '7 days'").execute(conn)?;
+        Ok(())
+    })
+}
@@ -0,0 +1,192 @@
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use tracing::{info, warn};
+use url::{Host, Url};
+
+use crate::error::ShortenerError;
+
+/// Rules a destination URL has to satisfy before it can be shortened
+pub struct UrlPolicy {
+    pub allowed_schemes: Vec<String>,
+    pub max_length: usize,
+    /// Domains our own short links live on; shortening them would create a redirect loop
+    pub own_domains: Vec<String>,
+    /// Third-party shorteners; shortening them would create a redirect chain
+    pub shortener_domains: Vec<String>,
+    pub blocklist: Arc<Blocklist>,
+}
+
+impl UrlPolicy {
+    /// Parses `raw` and checks it against every rule, returning the parsed URL on success.
+    pub fn check(&self, raw: &str) -> Result<Url, ShortenerError> {
+        if raw.len() > self.max_length {
+            return Err(ShortenerError::InvalidUrl(format!(
+                "URL is longer than {} characters",
+                self.max_length
+            )));
+        }
+
+        let url = Url::parse(raw).map_err(|err| ShortenerError::InvalidUrl(format!("Invalid URL: {}", err)))?;
+
+        if !self.allowed_schemes.iter().any(|scheme| scheme == url.scheme()) {
+            return Err(ShortenerError::BlockedUrl(format!("Scheme '{}' is not allowed", url.scheme())));
+        }
+
+        match url.host() {
+            None => return Err(ShortenerError::BlockedUrl("URL has no host".to_string())),
+            Some(Host::Ipv4(ip)) if is_non_public_ip(IpAddr::V4(ip)) => {
+                return Err(ShortenerError::BlockedUrl("Private and loopback addresses are not allowed".to_string()));
+            }
+            Some(Host::Ipv6(ip)) if is_non_public_ip(IpAddr::V6(ip)) => {
+                return Err(ShortenerError::BlockedUrl("Private and loopback addresses are not allowed".to_string()));
+            }
+            Some(Host::Domain(domain)) => self.check_domain(domain)?,
+            Some(_) => {}
+        }
+
+        Ok(url)
+    }
+
+    fn check_domain(&self, domain: &str) -> Result<(), ShortenerError> {
+        let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+
+        if domain_matches(&domain, "localhost") {
+            return Err(ShortenerError::BlockedUrl("Private and loopback addresses are not allowed".to_string()));
+        }
+        if self.own_domains.iter().any(|own| domain_matches(&domain, own)) {
+            return Err(ShortenerError::BlockedUrl("Shortening our own short links is not allowed".to_string()));
+        }
+        if self.shortener_domains.iter().any(|other| domain_matches(&domain, other)) {
+            return Err(ShortenerError::BlockedUrl("Links to other URL shorteners are not allowed".to_string()));
+        }
+        if self.blocklist.contains(&domain) {
+            return Err(ShortenerError::BlockedUrl(format!("Domain '{}' is blocked", domain)));
+        }
+
+        Ok(())
+    }
+}
+
+/// `domain` is `candidate` itself or one of its subdomains
+fn domain_matches(domain: &str, candidate: &str) -> bool {
+    domain == candidate
+        || (domain.len() > candidate.len()
+            && domain.ends_with(candidate)
+            && domain.as_bytes()[domain.len() - candidate.len() - 1] == b'.')
+}
+
+/// Loopback, private, link-local and other ranges that must never be a redirect target
+fn is_non_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_non_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_non_public_ipv4(mapped),
+            None => is_non_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_non_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [first, second, ..] = ip.octets();
+    ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || first == 0 // "this network"
+        || (first == 100 && (64..128).contains(&second)) // carrier-grade NAT, 100.64.0.0/10
+}
+
+fn is_non_public_ipv6(ip: Ipv6Addr) -> bool {
+    let first_segment = ip.segments()[0];
+    ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        || (first_segment & 0xfe00) == 0xfc00 // unique local, fc00::/7
+        || (first_segment & 0xffc0) == 0xfe80 // link-local, fe80::/10
+}
+
+/// Domains loaded from a blocklist file, one per line with `#` comments.
+/// Entries also block their subdomains.
+pub struct Blocklist {
+    path: Option<PathBuf>,
+    domains: RwLock<HashSet<String>>,
+}
+
+impl Blocklist {
+    /// Loads the file at `path` if given; a missing file starts out as an empty list
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let blocklist = Blocklist {
+            path,
+            domains: RwLock::new(HashSet::new()),
+        };
+        blocklist.reload();
+        blocklist
+    }
+
+    pub fn contains(&self, domain: &str) -> bool {
+        let domains = self.domains.read().unwrap();
+        // Walk up the labels so `ads.evil.com` is caught by an `evil.com` entry
+        let mut candidate = domain;
+        loop {
+            if domains.contains(candidate) {
+                return true;
+            }
+            match candidate.split_once('.') {
+                Some((_, parent)) => candidate = parent,
+                None => return false,
+            }
+        }
+    }
+
+    fn reload(&self) {
+        let Some(path) = &self.path else { return };
+
+        match std::fs::read_to_string(path) {
+            Ok(contents) => {
+                let domains: HashSet<String> = contents
+                    .lines()
+                    .map(|line| line.split('#').next().unwrap_or_default().trim())
+                    .filter(|line| !line.is_empty())
+                    .map(|line| line.trim_end_matches('.').to_ascii_lowercase())
+                    .collect();
+                info!("🛡️ Loaded {} blocked domains from {}", domains.len(), path.display());
+                *self.domains.write().unwrap() = domains;
+            }
+            Err(err) => warn!("⚠️ Failed to read blocklist {}: {}", path.display(), err),
+        }
+    }
+
+    fn modified_at(&self) -> Option<SystemTime> {
+        let path = self.path.as_ref()?;
+        std::fs::metadata(path).and_then(|meta| meta.modified()).ok()
+    }
+
+    /// Polls the file's modification time and reloads it whenever it changes
+    pub fn spawn_reloader(self: Arc<Self>, interval: Duration) {
+        if self.path.is_none() {
+            return;
+        }
+
+        tokio::spawn(async move {
+            let mut last_modified = self.modified_at();
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let modified = self.modified_at();
+                if modified != last_modified {
+                    last_modified = modified;
+                    // Keep the file read off the async workers
+                    let blocklist = self.clone();
+                    let _ = tokio::task::spawn_blocking(move || blocklist.reload()).await;
+                }
+            }
+        });
+    }
+}
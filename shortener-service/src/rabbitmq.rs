@@ -1,31 +1,121 @@
-use lapin::{options::*, BasicProperties};
-use tracing::info;
-use common::rabbitmq::connect_to_rabbitmq;
-
-
-pub async fn publish_to_queue(short_code: &str, original_url: &str) -> Result<(), lapin::Error> {
-    info!("📢 Publishing to RabbitMQ: {}:{}", short_code, original_url);
-
-    let rabbitmq_url = std::env::var("RABBITMQ_URL").expect("RABBITMQ_URL must be set");
-    info!("🐇 RabbitMQ URL: {}", rabbitmq_url);
-
-    // Use `connect_to_rabbitmq` instead of direct connection
-    let conn = connect_to_rabbitmq(&rabbitmq_url).await;
-    let channel = conn.create_channel().await?;
-    info!("📡 Channel created");
-
-    let payload = format!("{}:{}", short_code, original_url);
-    
-    channel
-        .basic_publish(
-            "", 
-            "url_queue", 
-            BasicPublishOptions::default(), 
-            payload.as_bytes(), 
-            BasicProperties::default(),
-        )
-        .await?;
-    
-    info!("✅ Message successfully published to queue");
+use std::sync::{Arc, Mutex};
+
+use common::events::LINK_EVENTS_EXCHANGE;
+use common::rabbitmq::{declare_link_events_exchange, AmqpManager};
+use lapin::{options::*, publisher_confirm::Confirmation, BasicProperties, Channel};
+use tracing::warn;
+
+/// Channels kept open between publishes
+const MAX_IDLE_CHANNELS: usize = 4;
+
+type PublishError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Publishes to the `link_events` exchange over the service's shared connection, reusing
+/// confirm-mode channels from a small pool. Channels left over from a lost connection are
+/// dropped and replaced by ones on the connection the manager reopened.
+pub struct Publisher {
+    amqp: Arc<AmqpManager>,
+    idle: Mutex<Vec<Channel>>,
+}
+
+impl Publisher {
+    pub fn new(amqp: Arc<AmqpManager>) -> Self {
+        Publisher {
+            amqp,
+            idle: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Opens a channel in publisher-confirm mode with the exchange declared; fails while disconnected
+    async fn open_channel(&self) -> Result<Channel, lapin::Error> {
+        let channel = self.amqp.create_channel().await?;
+        channel.confirm_select(ConfirmSelectOptions::default()).await?;
+        declare_link_events_exchange(&channel).await?;
+        Ok(channel)
+    }
+
+    async fn checkout(&self) -> Result<Channel, lapin::Error> {
+        loop {
+            let pooled = self.idle.lock().expect("publisher pool lock poisoned").pop();
+            match pooled {
+                Some(channel) if channel.status().connected() => return Ok(channel),
+                Some(_) => continue,
+                None => return self.open_channel().await,
+            }
+        }
+    }
+
+    fn checkin(&self, channel: Channel) {
+        if !channel.status().connected() {
+            return;
+        }
+        let mut idle = self.idle.lock().expect("publisher pool lock poisoned");
+        if idle.len() < MAX_IDLE_CHANNELS {
+            idle.push(channel);
+        } else {
+            drop(idle);
+            tokio::spawn(async move {
+                let _ = channel.close(200, "OK").await;
+            });
+        }
+    }
+
+    /// Publishes `(routing_key, payload)` messages in order and waits until the broker confirmed
+    /// every one of them. Fails when a message could not be routed to any queue, so events are kept
+    /// while no redirect-service replica is bound to the exchange (e.g. during a deploy). When the
+    /// channel turns out to be closed (e.g. a pooled one from before a broker restart) the batch is
+    /// retried once on a fresh channel, so the caller must tolerate duplicates.
+    pub async fn publish_confirmed(&self, messages: &[(&str, &[u8])]) -> Result<(), PublishError> {
+        let mut retried = false;
+        loop {
+            let channel = self.checkout().await?;
+            match publish_on(&channel, messages).await {
+                Ok(()) => {
+                    self.checkin(channel);
+                    return Ok(());
+                }
+                Err(err) if !retried && !channel.status().connected() => {
+                    warn!("⚠️ RabbitMQ channel closed while publishing, retrying: {}", err);
+                    retried = true;
+                }
+                Err(err) => {
+                    // Kept only if still open; unroutable or nacked messages leave the channel usable
+                    self.checkin(channel);
+                    return Err(err);
+                }
+            }
+        }
+    }
+}
+
+async fn publish_on(channel: &Channel, messages: &[(&str, &[u8])]) -> Result<(), PublishError> {
+    let mut confirms = Vec::with_capacity(messages.len());
+    for (routing_key, payload) in messages {
+        let confirm = channel
+            .basic_publish(
+                LINK_EVENTS_EXCHANGE,
+                routing_key,
+                // Have the broker return unroutable messages instead of confirming and dropping them
+                BasicPublishOptions {
+                    mandatory: true,
+                    ..BasicPublishOptions::default()
+                },
+                payload,
+                BasicProperties::default()
+                    .with_content_type("application/json".into())
+                    .with_delivery_mode(2), // persistent
+            )
+            .await?;
+        confirms.push(confirm);
+    }
+
+    for confirm in confirms {
+        match confirm.await? {
+            Confirmation::Ack(None) => {}
+            Confirmation::Ack(Some(_)) => return Err("No queue is bound to receive a link event".into()),
+            Confirmation::Nack(_) => return Err("RabbitMQ rejected a link event".into()),
+            Confirmation::NotRequested => return Err("Publish channel is not in confirm mode".into()),
+        }
+    }
     Ok(())
 }
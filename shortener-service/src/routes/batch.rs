@@ -0,0 +1,96 @@
+use axum::{extract::State, Json};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::AppState;
+use crate::error::{ErrorBody, ShortenerError};
+use crate::extract::JsonBody;
+use crate::routes::urlshort::{create_link, LinkOutcome, ShortenRequest, ShortenResponse};
+
+#[derive(Deserialize)]
+pub struct BatchShortenRequest {
+    pub items: Vec<ShortenRequest>,
+}
+
+/// Outcome of one batch item; exactly one of `result` and `error` is set
+#[derive(Serialize)]
+pub struct BatchItemResult {
+    /// Position of the item in the request
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<ShortenResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<BatchItemError>,
+}
+
+#[derive(Serialize)]
+pub struct BatchItemError {
+    /// HTTP status the item would have produced on `POST /shorten`
+    pub status: u16,
+    #[serde(flatten)]
+    pub body: ErrorBody,
+}
+
+#[derive(Serialize)]
+pub struct BatchShortenResponse {
+    pub results: Vec<BatchItemResult>,
+}
+
+/// Shorten many URLs at once. Items are stored independently, so one bad item does not fail
+/// the rest; results come back in request order and the relay is woken once for all created links.
+pub async fn shorten_batch(
+    State(state): State<Arc<AppState>>,
+    JsonBody(payload): JsonBody<BatchShortenRequest>,
+) -> Result<Json<BatchShortenResponse>, ShortenerError> {
+    if payload.items.is_empty() || payload.items.len() > state.batch_max_items {
+        return Err(ShortenerError::InvalidRequest(format!(
+            "A batch must contain between 1 and {} items",
+            state.batch_max_items
+        )));
+    }
+
+    // Up to `batch_max_items` synchronous inserts; keep them off the async workers
+    let worker_state = state.clone();
+    let (results, created) = tokio::task::spawn_blocking(move || shorten_items(&worker_state, &payload.items))
+        .await
+        .map_err(|err| ShortenerError::Internal(format!("Batch shortening task failed: {}", err)))??;
+
+    if created {
+        state.outbox.wake();
+    }
+
+    Ok(Json(BatchShortenResponse { results }))
+}
+
+/// Creates the items in order; also returns whether any link was newly created
+fn shorten_items(state: &AppState, items: &[ShortenRequest]) -> Result<(Vec<BatchItemResult>, bool), ShortenerError> {
+    let mut conn = state.db_pool.get()?;
+
+    let mut results = Vec::with_capacity(items.len());
+    let mut created = false;
+    for (index, item) in items.iter().enumerate() {
+        let result = match create_link(state, &mut conn, item) {
+            Ok(LinkOutcome::Created(new_entry)) => {
+                created = true;
+                BatchItemResult { index, result: Some(ShortenResponse::from(new_entry)), error: None }
+            }
+            Ok(LinkOutcome::Reused(existing)) => {
+                BatchItemResult { index, result: Some(ShortenResponse::from(existing)), error: None }
+            }
+            Err(err @ ShortenerError::DatabaseUnavailable) => {
+                // Every remaining item would fail the same way; earlier ones are already stored,
+                // so report them instead of failing the whole request
+                let error = || BatchItemError { status: err.status().as_u16(), body: err.body() };
+                results.extend((index..items.len()).map(|index| BatchItemResult { index, result: None, error: Some(error()) }));
+                break;
+            }
+            Err(err) => BatchItemResult {
+                index,
+                result: None,
+                error: Some(BatchItemError { status: err.status().as_u16(), body: err.body() }),
+            },
+        };
+        results.push(result);
+    }
+    Ok((results, created))
+}
@@ -0,0 +1,241 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use common::events::LinkEventKind;
+use diesel::prelude::*;
+use diesel::result::DatabaseErrorKind;
+use serde::{Deserialize, Deserializer};
+use std::sync::Arc;
+
+use crate::AppState;
+use crate::alias::validate_alias;
+use crate::canonical::canonicalize;
+use crate::error::ShortenerError;
+use crate::extract::JsonBody;
+use crate::models::url::UrlMappingModel;
+use crate::outbox;
+use crate::routes::lookup::UrlInfoResponse;
+use crate::routes::urlshort::{resolve_expiration, validate_redirect_type, ALIAS_CONSTRAINT};
+use crate::schema::url_mapping;
+use crate::schema::url_mapping::dsl::*;
+
+/// Identifies the caller; only the link owner may change it
+#[derive(Deserialize)]
+pub struct OwnerParams {
+    user_id: i32,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateLinkRequest {
+    pub long_url: Option<String>,
+    /// Omitted leaves the alias unchanged, `null` removes it
+    #[serde(default, deserialize_with = "present_or_null")]
+    pub alias: Option<Option<String>>,
+    pub expiration_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub never_expires: bool,
+    pub redirect_type: Option<i32>,
+}
+
+/// Tells an explicit `null` apart from a missing field
+fn present_or_null<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Columns touched by an edit; `None` leaves a column as it is
+#[derive(AsChangeset)]
+#[diesel(table_name = url_mapping)]
+struct LinkChanges {
+    long_url: Option<String>,
+    canonical_url: Option<Option<String>>,
+    alias: Option<Option<String>>,
+    expiration_date: Option<Option<NaiveDateTime>>,
+    redirect_type: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct SetDisabledRequest {
+    pub disabled: bool,
+}
+
+/// Load a link and make sure it belongs to `owner`.
+/// Soft-deleted links are treated as missing unless `include_deleted` is set.
+fn find_owned_link(
+    conn: &mut PgConnection,
+    code: &str,
+    owner: i32,
+    include_deleted: bool,
+) -> Result<UrlMappingModel, ShortenerError> {
+    let link = url_mapping
+        .find(code)
+        .first::<UrlMappingModel>(conn)
+        .optional()?
+        .filter(|link| include_deleted || link.deleted_at.is_none())
+        .ok_or_else(|| ShortenerError::LinkNotFound(code.to_string()))?;
+
+    if link.user_id != Some(owner) {
+        return Err(ShortenerError::Forbidden);
+    }
+    Ok(link)
+}
+
+/// Change the destination, alias, expiration and/or redirect status of an existing link.
+pub async fn update_link(
+    Path(code): Path<String>,
+    Query(params): Query<OwnerParams>,
+    State(state): State<Arc<AppState>>,
+    JsonBody(payload): JsonBody<UpdateLinkRequest>,
+) -> Result<Json<UrlInfoResponse>, ShortenerError> {
+    let mut changes = LinkChanges {
+        long_url: None,
+        canonical_url: None,
+        alias: None,
+        expiration_date: None,
+        redirect_type: payload.redirect_type.map(validate_redirect_type).transpose()?,
+    };
+
+    if let Some(new_url) = &payload.long_url {
+        let destination = state.policy.check(new_url)?;
+        let canonical = canonicalize(&destination, &state.tracking_params);
+        changes.long_url = Some(new_url.clone());
+        changes.canonical_url = Some(Some(canonical).filter(|c| c.len() <= state.policy.max_length));
+    }
+
+    if let Some(Some(requested_alias)) = &payload.alias {
+        validate_alias(requested_alias).map_err(ShortenerError::InvalidRequest)?;
+    }
+    changes.alias = payload.alias.clone();
+
+    if payload.expiration_time.is_some() || payload.never_expires {
+        changes.expiration_date = Some(resolve_expiration(payload.expiration_time, payload.never_expires)?);
+    }
+
+    if changes.long_url.is_none()
+        && changes.alias.is_none()
+        && changes.expiration_date.is_none()
+        && changes.redirect_type.is_none()
+    {
+        return Err(ShortenerError::InvalidRequest("Nothing to update".to_string()));
+    }
+
+    let mut conn = state.db_pool.get()?;
+    let previous = find_owned_link(&mut conn, &code, params.user_id, false)?;
+
+    // Aliases share the redirect namespace with generated codes, so check both columns
+    if let Some(Some(requested_alias)) = &changes.alias {
+        let taken = diesel::select(diesel::dsl::exists(
+            url_mapping
+                .filter(alias.eq(requested_alias).or(short_url.eq(requested_alias)))
+                .filter(short_url.ne(&code)),
+        ))
+        .get_result::<bool>(&mut conn)?;
+
+        if taken {
+            return Err(ShortenerError::AliasTaken(requested_alias.clone()));
+        }
+    }
+
+    let updated = conn
+        .transaction::<_, diesel::result::Error, _>(|conn| {
+            let updated = diesel::update(url_mapping.find(&code))
+                .set(&changes)
+                .get_result::<UrlMappingModel>(conn)?;
+
+            // The old alias must stop resolving and the new one must not serve a stale entry
+            let mut event = updated.event(LinkEventKind::Updated);
+            event.previous_alias = previous.alias.clone().filter(|old| updated.alias.as_ref() != Some(old));
+            outbox::enqueue(conn, &event)?;
+            Ok(updated)
+        })
+        .map_err(|err| match err {
+            // Another request claimed the alias between our check and the update
+            diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, ref info)
+                if info.constraint_name() == Some(ALIAS_CONSTRAINT) =>
+            {
+                ShortenerError::AliasTaken(changes.alias.clone().flatten().unwrap_or_default())
+            }
+            other => other.into(),
+        })?;
+    state.outbox.wake();
+
+    Ok(Json(UrlInfoResponse::from(updated)))
+}
+
+/// Soft-delete a link. It stops resolving immediately and can be restored
+/// within the configured retention window.
+pub async fn delete_link(
+    Path(code): Path<String>,
+    Query(params): Query<OwnerParams>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<UrlInfoResponse>, ShortenerError> {
+    let mut conn = state.db_pool.get()?;
+    find_owned_link(&mut conn, &code, params.user_id, false)?;
+
+    let deleted = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let deleted = diesel::update(url_mapping.find(&code))
+            .set(deleted_at.eq(Utc::now().naive_utc()))
+            .get_result::<UrlMappingModel>(conn)?;
+        outbox::enqueue(conn, &deleted.event(LinkEventKind::Deleted))?;
+        Ok(deleted)
+    })?;
+    state.outbox.wake();
+
+    Ok(Json(UrlInfoResponse::from(deleted)))
+}
+
+/// Pause or resume a link without deleting it.
+pub async fn set_link_disabled(
+    Path(code): Path<String>,
+    Query(params): Query<OwnerParams>,
+    State(state): State<Arc<AppState>>,
+    JsonBody(payload): JsonBody<SetDisabledRequest>,
+) -> Result<Json<UrlInfoResponse>, ShortenerError> {
+    let mut conn = state.db_pool.get()?;
+    find_owned_link(&mut conn, &code, params.user_id, false)?;
+
+    let kind = if payload.disabled { LinkEventKind::Disabled } else { LinkEventKind::Updated };
+    let updated = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let updated = diesel::update(url_mapping.find(&code))
+            .set(disabled.eq(payload.disabled))
+            .get_result::<UrlMappingModel>(conn)?;
+        outbox::enqueue(conn, &updated.event(kind))?;
+        Ok(updated)
+    })?;
+    state.outbox.wake();
+
+    Ok(Json(UrlInfoResponse::from(updated)))
+}
+
+/// Undo a soft delete, as long as it happened within the retention window.
+pub async fn restore_link(
+    Path(code): Path<String>,
+    Query(params): Query<OwnerParams>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<UrlInfoResponse>, ShortenerError> {
+    let mut conn = state.db_pool.get()?;
+    let link = find_owned_link(&mut conn, &code, params.user_id, true)?;
+
+    let Some(deleted_on) = link.deleted_at else {
+        return Err(ShortenerError::InvalidRequest("Link is not deleted".to_string()));
+    };
+    if deleted_on + state.restore_window < Utc::now().naive_utc() {
+        return Err(ShortenerError::RestoreWindowExpired(code));
+    }
+
+    let restored = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+        let restored = diesel::update(url_mapping.find(&code))
+            .set(deleted_at.eq(None::<NaiveDateTime>))
+            .get_result::<UrlMappingModel>(conn)?;
+        outbox::enqueue(conn, &restored.event(LinkEventKind::Updated))?;
+        Ok(restored)
+    })?;
+    state.outbox.wake();
+
+    Ok(Json(UrlInfoResponse::from(restored)))
+}
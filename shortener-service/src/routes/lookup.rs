@@ -1,7 +1,6 @@
 use axum::{
     extract::{Query, State},
     Json,
-    http::StatusCode,
 };
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -9,7 +8,8 @@ use std::sync::Arc;
 use chrono::{DateTime, Utc};
 use crate::models::url::UrlMappingModel;
 use crate::schema::url_mapping::dsl::*;
-use common::db::DbPool;
+use crate::AppState;
+use crate::error::ShortenerError;
 
 #[derive(Deserialize)]
 pub struct QueryParams {
@@ -25,6 +25,9 @@ pub struct UrlInfoResponse {
     pub creation_date: DateTime<Utc>,
     pub expiration_date: Option<DateTime<Utc>>,
     pub click_count: i32,
+    pub disabled: bool,
+    pub redirect_type: i32,
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 impl From<UrlMappingModel> for UrlInfoResponse {
@@ -40,6 +43,9 @@ impl From<UrlMappingModel> for UrlInfoResponse {
             creation_date: DateTime::<Utc>::from_naive_utc_and_offset(mapping.creation_date, Utc),
             expiration_date: mapping.expiration_date.map(|dt| DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc)),
             click_count: mapping.click_count,
+            disabled: mapping.disabled,
+            redirect_type: mapping.redirect_type,
+            deleted_at: mapping.deleted_at.map(|dt| DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc)),
         }
     }
 }
@@ -47,18 +53,18 @@ impl From<UrlMappingModel> for UrlInfoResponse {
 /// Retrieve all URL mappings for a given user ID.
 pub async fn get_urls_by_user_id(
     Query(params): Query<QueryParams>,
-    State(pool): State<Arc<DbPool>>,
-) -> Result<Json<Vec<UrlInfoResponse>>, StatusCode> {
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<UrlInfoResponse>>, ShortenerError> {
     use diesel::RunQueryDsl;
 
     // Get a database connection from the pool
-    let mut conn = pool.get().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut conn = state.db_pool.get()?;
 
     // Query the database for URL mappings matching the user_id
     let results = url_mapping
         .filter(user_id.eq(params.user_id))
-        .load::<UrlMappingModel>(&mut conn)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        .filter(deleted_at.is_null()) // Soft-deleted links only come back through restore
+        .load::<UrlMappingModel>(&mut conn)?;
 
     // Convert the results into the response format
     let response = results.into_iter().map(UrlInfoResponse::from).collect();
@@ -1,2 +1,5 @@
 pub mod urlshort;
-pub mod lookup;
\ No newline at end of file
+pub mod lookup;
+pub mod batch;
+pub mod links;
+pub mod health;
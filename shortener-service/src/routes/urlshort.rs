@@ -1,28 +1,59 @@
-use axum::{extract::State, Json, http::StatusCode};
+use axum::{extract::State, Json};
 use diesel::prelude::*;
+use diesel::result::DatabaseErrorKind;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use chrono::{DateTime, Duration, Utc};
-use url::Url;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use common::events::LinkEventKind;
 
-use common::db::DbPool;
+use crate::AppState;
 use crate::models::url::UrlMappingModel;
-use crate::{rabbitmq::publish_to_queue, schema::url_mapping::dsl::*};
-use crate::hashcode::generate_short_code;
+use crate::{outbox, schema::url_mapping::dsl::*};
+use crate::allocator::allocate_short_code;
+use crate::hashcode::CodeStrategy;
+use crate::alias::validate_alias;
+use crate::error::ShortenerError;
+use crate::extract::JsonBody;
+use crate::canonical::canonicalize;
+
+/// Lifetime applied when the caller does not ask for a specific expiration
+const DEFAULT_EXPIRATION_DAYS: i64 = 30;
+/// Bounds for caller-supplied expirations
+const MIN_EXPIRATION_MINUTES: i64 = 5;
+const MAX_EXPIRATION_DAYS: i64 = 5 * 365;
+/// How many freshly allocated codes to try before giving up on an insert
+const MAX_INSERT_ATTEMPTS: u32 = 5;
+/// Name of the UNIQUE constraint on `url_mapping.alias`
+pub const ALIAS_CONSTRAINT: &str = "url_mapping_alias_key";
+/// Redirect status used when the caller does not pick one
+pub const DEFAULT_REDIRECT_TYPE: i32 = 302;
+/// Statuses a link may redirect with
+const REDIRECT_TYPES: &[i32] = &[301, 302, 307, 308];
 
 #[derive(Deserialize, Serialize)]
 pub struct ShortenRequest {
     pub long_url: String,
     pub custom_alias: Option<String>,
     pub expiration_time: Option<DateTime<Utc>>,
+    /// Keep the link alive forever; cannot be combined with `expiration_time`
+    #[serde(default)]
+    pub never_expires: bool,
     pub user_id: Option<i32>,
+    /// Overrides the configured `CODE_STRATEGY` for this link
+    pub code_strategy: Option<CodeStrategy>,
+    /// Always mint a new code, even if the user already shortened the same destination.
+    /// Requests with `expiration_time` or `never_expires` always get a new code as well.
+    #[serde(default)]
+    pub allow_duplicate: bool,
+    /// HTTP status used for the redirect: 301, 302 (default), 307 or 308
+    pub redirect_type: Option<i32>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct ShortenResponse {
     pub short_code: String,
     pub short_url: String,
-    pub expiration_time: DateTime<Utc>,
+    pub expiration_time: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
 impl From<UrlMappingModel> for ShortenResponse {
@@ -35,75 +66,184 @@ impl From<UrlMappingModel> for ShortenResponse {
 
         ShortenResponse {
             short_code,
-            short_url: format!("http://localhost:8080/{}", other_short_url), // Full short URL
+            short_url: format!("http://localhost:8081/{}", other_short_url), // Full short URL
             expiration_time: mapping
                 .expiration_date
-                .map(|dt| DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc))
-                .unwrap_or_else(||Utc::now() +  Duration::days(30)), // Default to now if expiration is missing + 30 days
+                .map(|dt| DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc)), // None means the link never expires
             created_at: DateTime::<Utc>::from_naive_utc_and_offset(mapping.creation_date, Utc),
         }
     }
 }
-// Validate the URL
-pub fn is_valid_url(url: &str) -> bool {
-    Url::parse(url).is_ok()
-}   
 
-/// Shorten a URL and store it in the database.
-pub async fn shorten_url(
-    State(pool): State<Arc<DbPool>>,
-    Json(payload): Json<ShortenRequest>,
-) -> Result<Json<ShortenResponse>, StatusCode> {
-    
-    // Validate URL
-    if !is_valid_url(&payload.long_url) {
-        return Ok(Json(ShortenResponse {
-            short_code: "invalid".to_string(),
-            short_url: "https://example.com/400".to_string(), // Bad Request
-            created_at: Utc::now(), // Default to now
-            expiration_time: Utc::now() + Duration::days(30), // Default expiration
-        }));
+/// Resolve the expiration date to store, range-checking caller-supplied values.
+/// Returns `None` for links that never expire.
+pub fn resolve_expiration(
+    requested: Option<DateTime<Utc>>,
+    never_expires: bool,
+) -> Result<Option<NaiveDateTime>, ShortenerError> {
+    let now = Utc::now();
+    match (requested, never_expires) {
+        (Some(_), true) => Err(ShortenerError::InvalidRequest(
+            "expiration_time cannot be combined with never_expires".to_string(),
+        )),
+        (None, true) => Ok(None),
+        (None, false) => Ok(Some((now + Duration::days(DEFAULT_EXPIRATION_DAYS)).naive_utc())),
+        (Some(at), false) => {
+            if at < now + Duration::minutes(MIN_EXPIRATION_MINUTES)
+                || at > now + Duration::days(MAX_EXPIRATION_DAYS)
+            {
+                return Err(ShortenerError::InvalidRequest(format!(
+                    "expiration_time must be between {} minutes and {} days from now",
+                    MIN_EXPIRATION_MINUTES, MAX_EXPIRATION_DAYS
+                )));
+            }
+            Ok(Some(at.naive_utc()))
+        }
+    }
+}
+
+/// Reject redirect statuses other than 301, 302, 307 and 308
+pub fn validate_redirect_type(status: i32) -> Result<i32, ShortenerError> {
+    if REDIRECT_TYPES.contains(&status) {
+        Ok(status)
+    } else {
+        Err(ShortenerError::InvalidRequest(format!(
+            "redirect_type must be one of {:?}",
+            REDIRECT_TYPES
+        )))
     }
+}
 
-    
-    let mut conn = pool.get().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+/// Result of `create_link`
+pub enum LinkOutcome {
+    Created(UrlMappingModel),
+    /// The user's existing active link for the same canonical destination
+    Reused(UrlMappingModel),
+}
 
+/// Validate a shorten request and store it, reusing the owner's existing link when possible.
+/// Shared by the single and batch endpoints. The `created` event is written to the outbox with the
+/// link; waking the relay is left to the caller.
+pub fn create_link(
+    state: &AppState,
+    conn: &mut PgConnection,
+    payload: &ShortenRequest,
+) -> Result<LinkOutcome, ShortenerError> {
+    // Validate the destination against the URL policy
+    let destination = state.policy.check(&payload.long_url)?;
+    let canonical = canonicalize(&destination, &state.tracking_params);
 
-    // Fetch existing short codes to prevent collisions
-    let existing_codes: Vec<String> = url_mapping
-        .select(short_url)
-        .load::<String>(&mut conn)
-        .unwrap_or_default();
+    let expiration = resolve_expiration(payload.expiration_time, payload.never_expires)?;
 
-    let short_code_value = generate_short_code(&payload.long_url, &existing_codes.into_iter().collect());
+    if let Some(requested_alias) = &payload.custom_alias {
+        validate_alias(requested_alias).map_err(ShortenerError::InvalidRequest)?;
+    }
 
-    let new_entry = UrlMappingModel {
-        short_url: short_code_value.clone(), // Use the generated short code
-        alias: None, // No alias by default; can be set later if needed
-        long_url: payload.long_url.clone(),
-        creation_date: Utc::now().naive_utc(),
-        expiration_date: Some(Utc::now().naive_utc() + Duration::days(30)), // Set expiration 30 days later
-        user_id: None, // Anonymous by default; can be linked to a user if authenticated
-        click_count: 0, // Initialize click count to 0
-    };
+    let status = validate_redirect_type(payload.redirect_type.unwrap_or(DEFAULT_REDIRECT_TYPE))?;
 
-    conn.transaction::<_, diesel::result::Error, _>(|conn| {
-        diesel::insert_into(url_mapping)
-            .values(&new_entry)
-            .execute(conn)?;
+    // Hand back the user's existing active link instead of minting a duplicate. Only for the default
+    // lifetime: an existing link could not honor an explicit `expiration_time` or `never_expires`.
+    let default_lifetime = payload.expiration_time.is_none() && !payload.never_expires;
+    if let (Some(owner), None, false, true) =
+        (payload.user_id, &payload.custom_alias, payload.allow_duplicate, default_lifetime)
+    {
+        let existing = url_mapping
+            .filter(user_id.eq(owner))
+            .filter(canonical_url.eq(&canonical))
+            .filter(deleted_at.is_null())
+            .filter(disabled.eq(false))
+            .filter(redirect_type.eq(status))
+            .filter(expiration_date.is_null().or(expiration_date.gt(Utc::now().naive_utc())))
+            .order(creation_date.desc())
+            .first::<UrlMappingModel>(conn)
+            .optional()?;
 
-        Ok(())
-    }).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if let Some(existing) = existing {
+            return Ok(LinkOutcome::Reused(existing));
+        }
+    }
 
-    // Publish to RabbitMQ (async, but errors don't break transaction)
-    if let Err(err) = publish_to_queue(&short_code_value, &payload.long_url).await {
-        eprintln!("⚠️ Failed to publish to RabbitMQ: {}", err);
+    // Aliases share the redirect namespace with generated codes, so check both columns
+    if let Some(requested_alias) = &payload.custom_alias {
+        let taken = diesel::select(diesel::dsl::exists(
+            url_mapping.filter(alias.eq(requested_alias).or(short_url.eq(requested_alias))),
+        ))
+        .get_result::<bool>(conn)?;
+
+        if taken {
+            return Err(ShortenerError::AliasTaken(requested_alias.clone()));
+        }
     }
 
-    Ok(Json(ShortenResponse {
-        short_code: short_code_value.clone(),
-        short_url: format!("http://localhost:8081/{}", short_code_value),
-        created_at: Utc::now(),
-        expiration_time: Utc::now() + Duration::days(30), // Default expiration
-    }))
+    let generator = state
+        .codes
+        .get(payload.code_strategy.unwrap_or(state.codes.default_strategy));
+
+    let mut attempts = 0;
+    loop {
+        let short_code_value = allocate_short_code(conn, generator, &payload.long_url, attempts)?;
+        attempts += 1;
+
+        let new_entry = UrlMappingModel {
+            short_url: short_code_value, // Use the allocated short code
+            alias: payload.custom_alias.clone(),
+            long_url: payload.long_url.clone(),
+            creation_date: Utc::now().naive_utc(),
+            expiration_date: expiration,
+            user_id: payload.user_id,
+            click_count: 0, // Initialize click count to 0
+            // Punycode can push the canonical form past the column size; such links just aren't deduplicated
+            canonical_url: Some(canonical.clone()).filter(|c| c.len() <= state.policy.max_length),
+            deleted_at: None,
+            disabled: false,
+            redirect_type: status,
+        };
+
+        let inserted = conn.transaction::<_, diesel::result::Error, _>(|conn| {
+            diesel::insert_into(url_mapping)
+                .values(&new_entry)
+                .execute(conn)?;
+            outbox::enqueue(conn, &new_entry.event(LinkEventKind::Created))?;
+
+            Ok(())
+        });
+
+        match inserted {
+            Ok(()) => return Ok(LinkOutcome::Created(new_entry)),
+            // Another request claimed the alias between our check and the insert
+            Err(diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, info))
+                if info.constraint_name() == Some(ALIAS_CONSTRAINT) =>
+            {
+                return Err(ShortenerError::AliasTaken(new_entry.alias.unwrap_or_default()));
+            }
+            // The candidate code is already taken; ask the generator for another one
+            Err(diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _))
+                if attempts < MAX_INSERT_ATTEMPTS => continue,
+            // Every candidate was taken, which means the strategy's code space is nearly full
+            Err(diesel::result::Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
+                return Err(ShortenerError::CodeSpaceExhausted(format!(
+                    "{} consecutive candidates were already taken",
+                    MAX_INSERT_ATTEMPTS
+                )));
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
+/// Shorten a URL and store it in the database.
+pub async fn shorten_url(
+    State(state): State<Arc<AppState>>,
+    JsonBody(payload): JsonBody<ShortenRequest>,
+) -> Result<Json<ShortenResponse>, ShortenerError> {
+    let mut conn = state.db_pool.get()?;
+
+    let new_entry = match create_link(&state, &mut conn, &payload)? {
+        LinkOutcome::Created(new_entry) => new_entry,
+        LinkOutcome::Reused(existing) => return Ok(Json(ShortenResponse::from(existing))),
+    };
+
+    state.outbox.wake();
+
+    Ok(Json(ShortenResponse::from(new_entry)))
 }
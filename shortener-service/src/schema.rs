@@ -7,6 +7,10 @@ diesel::table! {
         expiration_date -> Nullable<Timestamp>,
         user_id -> Nullable<Int4>,  
         click_count -> Int4,
+        canonical_url -> Nullable<Varchar>,
+        deleted_at -> Nullable<Timestamp>,
+        disabled -> Bool,
+        redirect_type -> Int4,
     }
 }
 